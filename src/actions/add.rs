@@ -1,22 +1,74 @@
 use crate::{
+    cli::InstallOptions,
     environment::Environment,
-    github,
+    selection,
+    signature::{CosignVerification, Verification},
+    source::{self, ToolSource},
     system::System,
-    tool::Tool,
+    template::UrlTemplate,
+    tool::{Binary, Tool},
     version::{parse_version, Version},
 };
+use regex::Regex;
 use skim::{
     prelude::{SkimItemReader, SkimOptionsBuilder},
     Skim,
 };
-use std::io::Cursor;
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+use tabled::{
+    settings::{Panel, Style},
+    Table, Tabled,
+};
 use tracing::{error, info};
 
 pub struct Patterns {
     pub asset: Option<String>,
     pub file: Option<String>,
+    /// Executables linked next to the main one.
+    pub binaries: Vec<Binary>,
+}
+
+/// Where a tool is installed from, when it isn't the public instance of a forge.
+pub struct SourceOptions {
+    pub base_url: Option<String>,
+    pub template: Option<UrlTemplate>,
+    /// Source building the tool when a release has no asset for the platform.
+    pub fallback: Option<String>,
+}
+
+/// Files containing the public keys used to verify a tool's release signatures.
+pub struct SignatureKeys {
+    pub minisign: Option<PathBuf>,
+    pub cosign: Option<PathBuf>,
+    pub gpg: Option<PathBuf>,
 }
 
+impl SignatureKeys {
+    fn load(&self) -> super::Result<Verification> {
+        let read_key = |file_path: &Option<PathBuf>| match file_path {
+            Some(file_path) => std::fs::read_to_string(file_path)
+                .map(Some)
+                .map_err(|read_err| super::ActionsError::KeyRead {
+                    file_path: file_path.to_path_buf(),
+                    source: read_err,
+                }),
+            None => Ok(None),
+        };
+        Ok(Verification {
+            minisign: read_key(&self.minisign)?,
+            cosign: read_key(&self.cosign)?.map(|key| CosignVerification {
+                key: Some(key),
+                ..Default::default()
+            }),
+            gpg: read_key(&self.gpg)?,
+        })
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn add_new_tool(
     env: &mut Environment,
     name: &'_ str,
@@ -25,6 +77,10 @@ pub async fn add_new_tool(
     alias: Option<String>,
     show: bool,
     pre_release: bool,
+    explain: bool,
+    source_options: SourceOptions,
+    keys: SignatureKeys,
+    options: &'_ InstallOptions,
 ) -> super::Result<()> {
     let split_name: Vec<&str> = name.split('@').collect();
     let org_repo = if split_name.len() > 1 {
@@ -32,20 +88,53 @@ pub async fn add_new_tool(
     } else {
         name
     };
-    let split_org_repo: Vec<&str> = org_repo.split('/').collect();
-    let owner = split_org_repo[0];
-    let repo = split_org_repo[1];
-    let alias = alias.unwrap_or_else(|| repo.to_string());
+    // keep the source already configured in the environment unless another one was provided
+    let existing_tool = env.tools.iter().find(|t| t.name == org_repo);
+    let base_url = source_options
+        .base_url
+        .or_else(|| existing_tool.and_then(|t| t.base_url.clone()));
+    let template = source_options
+        .template
+        .or_else(|| existing_tool.and_then(|t| t.template.clone()));
+    let fallback = source_options
+        .fallback
+        .or_else(|| existing_tool.and_then(|t| t.fallback.clone()));
+    if let Some(fallback) = &fallback {
+        super::fallback_source(fallback, system)?;
+    }
+    let binaries = match patterns.binaries {
+        binaries if binaries.is_empty() => existing_tool
+            .map(|t| t.binaries.clone())
+            .unwrap_or_default(),
+        binaries => binaries,
+    };
+    let tool_source = source::for_tool(
+        &Tool {
+            name: org_repo.to_string(),
+            base_url: base_url.clone(),
+            template: template.clone(),
+            ..Default::default()
+        },
+        system,
+    )?;
+    let alias = alias.unwrap_or_else(|| tool_source.default_alias().to_string());
 
     let asset_pattern = &patterns.asset.unwrap_or_default();
+    if !asset_pattern.is_empty() {
+        Regex::new(asset_pattern).map_err(|source| super::ActionsError::InvalidAssetPattern {
+            pattern: asset_pattern.to_string(),
+            source,
+        })?;
+    }
     let file_pattern = &patterns.file.unwrap_or_else(|| alias.clone());
+    let verification = keys.load()?;
 
-    info!("Owner `{owner}`, Repo `{repo}`, Alias `{alias}`, Pattern `{asset_pattern}`, Filter `{file_pattern}`");
+    info!("Source `{tool_source:?}`, Alias `{alias}`, Pattern `{asset_pattern}`, Filter `{file_pattern}`");
 
     let versions: Vec<String> = if split_name.len() > 1 {
         vec![split_name[1].to_string()]
     } else {
-        let versions = match github::get_repo_releases(owner, repo, pre_release).await {
+        let versions = match tool_source.list_versions(pre_release).await {
             Ok(res) => res,
             Err(e) => return Err(e.into()),
         };
@@ -80,21 +169,146 @@ pub async fn add_new_tool(
         }
     };
 
+    if explain {
+        for version in versions.iter() {
+            explain_asset_selection(&*tool_source, org_repo, version, system, asset_pattern)
+                .await?;
+        }
+        return Ok(());
+    }
+
     for version in versions.iter() {
         let parsed_version = parse_version(version);
 
-        let tool = Tool::new(
+        let mut tool = Tool::new(
             org_repo,
             &alias,
             &Version::Latest,
             asset_pattern,
             file_pattern,
         );
+        tool.base_url = base_url.clone();
+        tool.template = template.clone();
+        tool.fallback = fallback.clone();
+        tool.binaries = binaries.clone();
+        tool.version_req = match &parsed_version {
+            Version::Requirement(requirement) => Some(requirement.to_string()),
+            _ => None,
+        };
+        // keep the keys already configured in the environment unless new ones were provided
+        tool.verify = match env.tools.iter().find(|t| t.name == org_repo) {
+            Some(existing) if verification.is_empty() => existing.verify.clone(),
+            _ => verification.clone(),
+        };
 
-        match super::handle_tool_install(env, &tool, system, Some(parsed_version)).await {
+        match super::handle_tool_install(env, &tool, system, Some(parsed_version), options).await {
             Ok(_) => println!("Installation of tool {} complete.", &tool.name),
             Err(install_err) => error!("{:?}", install_err),
         }
     }
     Ok(())
 }
+
+/// Show how the assets of a release score for the platform, without installing anything.
+async fn explain_asset_selection(
+    tool_source: &'_ dyn ToolSource,
+    name: &'_ str,
+    version: &'_ str,
+    system: &'_ System,
+    asset_pattern: &'_ str,
+) -> super::Result<()> {
+    let tag = tool_source.resolve_version(&parse_version(version)).await?;
+    if tool_source.toolchain().is_some() {
+        println!("{name}@{tag} is installed with a toolchain, there are no assets to choose from.");
+        return Ok(());
+    }
+    let artifacts = tool_source.list_artifacts(&tag).await?;
+    let scores = tool_source.score_artifacts(&artifacts, system, asset_pattern);
+
+    #[derive(Tabled)]
+    struct ExplainedAsset {
+        #[tabled(rename = "Asset")]
+        name: String,
+        #[tabled(rename = "Score")]
+        score: String,
+        #[tabled(rename = "Reasons")]
+        reasons: String,
+    }
+    let rows = scores.iter().map(|score| ExplainedAsset {
+        name: score.artifact.name.to_string(),
+        score: if score.eligible {
+            score.points.to_string()
+        } else {
+            format!("{} (excluded)", score.points)
+        },
+        reasons: score
+            .reasons
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n"),
+    });
+    println!(
+        "{}",
+        Table::new(rows)
+            .with(Panel::header(match system.libc {
+                Some(libc) => format!("Assets of {name}@{tag} for {} ({libc})", system.platform()),
+                None => format!("Assets of {name}@{tag} for {}", system.platform()),
+            }))
+            .with(Style::rounded())
+    );
+    let best = selection::best_assets(&scores);
+    match best.as_slice() {
+        [] => println!("No asset of {name}@{tag} is eligible for {}.", system.platform()),
+        [best] => println!("{} would be installed.", best.artifact.name),
+        [first, ..] => println!(
+            "{} assets tie, vers asks which one to install when run in a terminal and installs {} otherwise.",
+            best.len(),
+            first.artifact.name
+        ),
+    }
+    Ok(())
+}
+
+/// Add a tool from an archive or directory on disk without any network access, `name` is used
+/// like the name of a downloaded tool and can include a version, `acme/mytool@1.2.3`.
+pub async fn add_local_tool(
+    env: &mut Environment,
+    file_path: &'_ Path,
+    name: &'_ str,
+    patterns: Patterns,
+    alias: Option<String>,
+) -> super::Result<()> {
+    let (tool_name, version) = match name.split_once('@') {
+        Some((tool_name, version)) => (tool_name, parse_version(version)),
+        None => (name, Version::Simple("local".to_string())),
+    };
+    // the file is remembered so the tool can be installed again by `sync` and `update`
+    let file_path =
+        file_path
+            .canonicalize()
+            .map_err(|read_err| super::ActionsError::LocalFileRead {
+                file_path: file_path.to_path_buf(),
+                source: read_err,
+            })?;
+    let alias = alias.unwrap_or_else(|| {
+        tool_name
+            .rsplit('/')
+            .next()
+            .unwrap_or(tool_name)
+            .to_string()
+    });
+    let asset_pattern = &patterns.asset.unwrap_or_default();
+    let file_pattern = &patterns.file.unwrap_or_else(|| alias.clone());
+
+    info!(
+        "Installing {tool_name}@{version} from {}",
+        file_path.display()
+    );
+    let mut tool = Tool::new(tool_name, &alias, &version, asset_pattern, file_pattern);
+    tool.from_file = Some(file_path.clone());
+    tool.binaries = patterns.binaries;
+    super::install_local_tool(env, &tool, version, &file_path).await?;
+    println!("Installation of tool {} complete.", &tool.name);
+    Ok(())
+}
@@ -26,10 +26,15 @@ pub async fn list_tools(
         alias: &'a str,
         #[tabled(rename = "Version")]
         version: &'a str,
+        #[tabled(rename = "Requirement")]
+        requirement: &'a str,
     }
     impl<'a> std::fmt::Display for ListTool<'a> {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            write!(f, "{}@{}", self.name, self.version)
+            match self.requirement {
+                "" => write!(f, "{}@{}", self.name, self.version),
+                requirement => write!(f, "{}@{} ({requirement})", self.name, self.version),
+            }
         }
     }
 
@@ -43,6 +48,7 @@ pub async fn list_tools(
                         name: &t.name,
                         alias: &t.alias,
                         version: tt,
+                        requirement: t.version_req.as_deref().unwrap_or_default(),
                     })
                     .collect()
             } else {
@@ -50,6 +56,7 @@ pub async fn list_tools(
                     name: &t.name,
                     alias: &t.alias,
                     version: &t.current_version,
+                    requirement: t.version_req.as_deref().unwrap_or_default(),
                 }]
             }
         })
@@ -6,7 +6,7 @@ mod remove;
 mod sync;
 mod update;
 
-pub use add::{add_new_tool, Patterns};
+pub use add::{add_local_tool, add_new_tool, Patterns, SignatureKeys, SourceOptions};
 pub use completions::generate_completions;
 pub use env::show_env_config;
 pub use list::list_tools;
@@ -15,13 +15,22 @@ pub use sync::sync_tools;
 pub use update::{update_tools, UpdateType};
 
 use crate::{
+    checksum,
+    cli::InstallOptions,
+    dirs,
+    download::{self, DownloadAuth, DownloadError},
     environment::{Environment, EnvironmentError},
-    github::{self, GitHubError},
+    lockfile::LockedAsset,
+    signature::{self, SignatureError, SignatureKind},
+    source::{self, Artifact, SourceError},
     system::{OperatingSystem, PlatformArchitecture, System},
     tool::Tool,
-    version::Version,
+    toolchain::Toolchain,
+    version::{self, parse_version, Version},
 };
+use std::path::{Path, PathBuf};
 use thiserror::Error;
+use tracing::{info, warn};
 
 #[derive(Debug, Error)]
 pub enum ActionsError {
@@ -42,6 +51,11 @@ pub enum ActionsError {
     EmptyEnvironment(String),
     #[error("Unable to find release for {0}")]
     ReleaseNotFound(String),
+    #[error("No release of {tool_name} satisfies {requirement}")]
+    NoMatchingVersion {
+        tool_name: String,
+        requirement: String,
+    },
     #[error("Unable to find asset for {tool_name}@{version} for OS: {os}; Arch: {arch}")]
     AssetNotFound {
         tool_name: String,
@@ -49,37 +63,361 @@ pub enum ActionsError {
         arch: PlatformArchitecture,
         os: OperatingSystem,
     },
-    #[error("Error with the GitHub API {0}")]
-    GitHub(#[from] GitHubError),
+    #[error("{tool_name} is built by a toolchain for this machine, it can't be installed for {platform}")]
+    ForeignToolchain { tool_name: String, platform: String },
+    #[error("Failed to calculate the checksum of '{file_path}'. {source}")]
+    Checksum {
+        file_path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Invalid URL '{url}' for {tool_name}. {message}")]
+    InvalidUrl {
+        tool_name: String,
+        url: String,
+        message: String,
+    },
+    #[error("Asset '{asset_name}' of {tool_name} does not match the lock file, expected {field} '{expected}' but got '{actual}'")]
+    LockMismatch {
+        tool_name: String,
+        asset_name: String,
+        field: &'static str,
+        expected: String,
+        actual: String,
+    },
+    #[error("No checksum was found in the release for asset '{0}'")]
+    ChecksumNotFound(String),
+    #[error("Checksum of '{asset_name}' does not match, expected '{expected}' but got '{actual}'")]
+    ChecksumMismatch {
+        asset_name: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Failed to read checksum file '{file_path}'. {source}")]
+    ChecksumFileRead {
+        file_path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{tool_name} is not in the lock file for {platform}, sync with --allow-unlocked to resolve it again")]
+    NotLocked { tool_name: String, platform: String },
+    #[error("{tool_name}@{version} does not match the lock file, it locks version {locked_version}, sync with --allow-unlocked to resolve it again")]
+    LockedVersionMismatch {
+        tool_name: String,
+        version: String,
+        locked_version: String,
+    },
+    #[error("Failed to sync tools: {}", .0.join(", "))]
+    SyncFailed(Vec<String>),
+    #[error("Unable to read '{file_path}'. {source}")]
+    LocalFileRead {
+        file_path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to read key file '{file_path}'. {source}")]
+    KeyRead {
+        file_path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Signature verification failed. {0}")]
+    Signature(#[from] SignatureError),
+    #[error("'{0}' can't be used as a fallback, only sources installing tools with a toolchain like `cargo:` or `go:` can")]
+    InvalidFallback(String),
+    #[error("Asset pattern '{pattern}' is not a valid regular expression. {source}")]
+    InvalidAssetPattern {
+        pattern: String,
+        source: regex::Error,
+    },
+    #[error("Error getting releases {0}")]
+    Source(#[from] SourceError),
+    #[error("Download error {0}")]
+    Download(#[from] DownloadError),
     #[error("Environment error {0}")]
     Environment(#[from] EnvironmentError),
 }
 
 type Result<T, E = ActionsError> = std::result::Result<T, E>;
 
+fn get_tool_version_dir(env: &'_ Environment, tool: &'_ Tool, version: &'_ Version) -> PathBuf {
+    dirs::get_tool_version_download_dir(Path::new(&env.base_dir), &tool.name, &version.as_tag())
+}
+
+fn lock_asset(
+    system: &'_ System,
+    asset_name: &'_ str,
+    asset_url: &'_ str,
+    asset_path: &'_ Path,
+) -> Result<LockedAsset> {
+    let to_checksum_err = |source| ActionsError::Checksum {
+        file_path: asset_path.to_path_buf(),
+        source,
+    };
+    Ok(LockedAsset {
+        platform: system.platform(),
+        name: asset_name.to_string(),
+        url: asset_url.to_string(),
+        size: std::fs::metadata(asset_path)
+            .map_err(to_checksum_err)?
+            .len(),
+        sha256: checksum::sha256_file(asset_path).map_err(to_checksum_err)?,
+    })
+}
+
+/// Verify a downloaded asset against the checksum files published in the same release.
+///
+/// The asset is deleted when the checksum doesn't match so it can never be extracted, a release
+/// without any checksum for the asset is only an error when checksums are required.
+async fn verify_release_checksum(
+    artifacts: &'_ [Artifact],
+    asset: &'_ Artifact,
+    asset_path: &'_ Path,
+    options: &'_ InstallOptions,
+    auth: Option<&'_ DownloadAuth>,
+) -> Result<()> {
+    let release_asset_names: Vec<&str> = artifacts.iter().map(|a| a.name.as_str()).collect();
+    let checksum_dir = asset_path.parent().unwrap_or_else(|| Path::new("."));
+
+    for checksum_name in checksum::find_checksum_asset_names(&asset.name, &release_asset_names) {
+        let checksum_asset = match artifacts.iter().find(|a| a.name == checksum_name) {
+            Some(checksum_asset) => checksum_asset,
+            None => continue,
+        };
+        let checksum_path = download::download_asset(
+            &checksum_asset.url,
+            &checksum_asset.name,
+            checksum_dir,
+            &options.retry_policy(),
+            auth,
+        )
+        .await?;
+        let contents = std::fs::read_to_string(&checksum_path);
+        let _ = std::fs::remove_file(&checksum_path);
+        let contents = contents.map_err(|read_err| ActionsError::ChecksumFileRead {
+            file_path: checksum_path.to_path_buf(),
+            source: read_err,
+        })?;
+
+        if let Some(expected) = checksum::parse_checksum(&contents, &asset.name) {
+            let actual = checksum::digest_file_like(asset_path, &expected).map_err(|source| {
+                ActionsError::Checksum {
+                    file_path: asset_path.to_path_buf(),
+                    source,
+                }
+            })?;
+            if actual != expected {
+                let _ = std::fs::remove_file(asset_path);
+                return Err(ActionsError::ChecksumMismatch {
+                    asset_name: asset.name.to_string(),
+                    expected,
+                    actual,
+                });
+            }
+            info!(
+                "Verified checksum of {} using {}",
+                &asset.name, checksum_name
+            );
+            return Ok(());
+        }
+    }
+
+    if options.require_checksums {
+        let _ = std::fs::remove_file(asset_path);
+        Err(ActionsError::ChecksumNotFound(asset.name.to_string()))
+    } else {
+        warn!(
+            "No checksum available for {}, skipping verification",
+            &asset.name
+        );
+        Ok(())
+    }
+}
+
+/// Verify the detached signatures of a downloaded asset with the keys configured for the tool.
+///
+/// A signature over the asset itself is preferred, otherwise a signed checksum file that lists the
+/// asset is used. Every kind of signature with a configured key has to be present and valid.
+async fn verify_release_signatures(
+    artifacts: &'_ [Artifact],
+    asset: &'_ Artifact,
+    asset_path: &'_ Path,
+    tool: &'_ Tool,
+    options: &'_ InstallOptions,
+    auth: Option<&'_ DownloadAuth>,
+) -> Result<()> {
+    if tool.verify.is_empty() {
+        return Ok(());
+    }
+    let release_asset_names: Vec<&str> = artifacts.iter().map(|a| a.name.as_str()).collect();
+    let download_dir = asset_path.parent().unwrap_or_else(|| Path::new("."));
+    let retry_policy = &options.retry_policy();
+    let download_release_asset = |name: &str| {
+        let release_asset = artifacts.iter().find(|a| a.name == name).cloned();
+        async move {
+            match release_asset {
+                Some(a) => Ok(Some(
+                    download::download_asset(&a.url, &a.name, download_dir, retry_policy, auth)
+                        .await?,
+                )),
+                None => Ok::<_, ActionsError>(None),
+            }
+        }
+    };
+
+    let signed_names: Vec<&str> = std::iter::once(asset.name.as_str())
+        .chain(checksum::find_checksum_asset_names(
+            &asset.name,
+            &release_asset_names,
+        ))
+        .collect();
+    for kind in tool.verify.kinds() {
+        let (signed_name, signature_name) = signed_names
+            .iter()
+            .find_map(|signed_name| {
+                signature::find_signature_asset_name(kind, signed_name, &release_asset_names)
+                    .map(|signature_name| (*signed_name, signature_name))
+            })
+            .ok_or_else(|| SignatureError::NotFound {
+                kind,
+                asset_name: asset.name.to_string(),
+            })?;
+
+        let mut downloaded: Vec<PathBuf> = Vec::new();
+        let result = async {
+            let signature_path = download_release_asset(signature_name)
+                .await?
+                .expect("signature is part of the release");
+            downloaded.push(signature_path.clone());
+            let certificate_path = match (kind, &tool.verify.cosign) {
+                (SignatureKind::Cosign, Some(cosign)) if cosign.key.is_none() => {
+                    match signature::find_certificate_asset_name(signed_name, &release_asset_names)
+                    {
+                        Some(name) => download_release_asset(name).await?,
+                        None => None,
+                    }
+                }
+                _ => None,
+            };
+            downloaded.extend(certificate_path.iter().cloned());
+
+            if signed_name == asset.name {
+                tool.verify.verify(
+                    kind,
+                    asset_path,
+                    &signature_path,
+                    certificate_path.as_deref(),
+                )?;
+                return Ok(());
+            }
+
+            // the signature covers a checksum file, the asset has to be listed in it
+            let checksum_path = download_release_asset(signed_name)
+                .await?
+                .expect("checksum file is part of the release");
+            downloaded.push(checksum_path.clone());
+            tool.verify.verify(
+                kind,
+                &checksum_path,
+                &signature_path,
+                certificate_path.as_deref(),
+            )?;
+            let contents = std::fs::read_to_string(&checksum_path).map_err(|read_err| {
+                ActionsError::ChecksumFileRead {
+                    file_path: checksum_path.to_path_buf(),
+                    source: read_err,
+                }
+            })?;
+            let expected = checksum::parse_checksum(&contents, &asset.name)
+                .ok_or_else(|| ActionsError::ChecksumNotFound(asset.name.to_string()))?;
+            let actual = checksum::digest_file_like(asset_path, &expected).map_err(|source| {
+                ActionsError::Checksum {
+                    file_path: asset_path.to_path_buf(),
+                    source,
+                }
+            })?;
+            if actual != expected {
+                return Err(ActionsError::ChecksumMismatch {
+                    asset_name: asset.name.to_string(),
+                    expected,
+                    actual,
+                });
+            }
+            Ok(())
+        }
+        .await;
+
+        for file in downloaded {
+            let _ = std::fs::remove_file(file);
+        }
+        if result.is_err() {
+            let _ = std::fs::remove_file(asset_path);
+        }
+        result?;
+    }
+    Ok(())
+}
+
 async fn handle_tool_install(
     env: &mut Environment,
     tool: &'_ Tool,
     system: &'_ System,
     version: Option<Version>,
+    options: &'_ InstallOptions,
 ) -> Result<()> {
-    let split_org_repo: Vec<&str> = tool.name.split('/').collect();
-    let owner = split_org_repo[0];
-    let repo = split_org_repo[1];
-
-    let version = match version {
-        Some(v) => v,
-        None => match github::get_latest_release_tag(owner, repo).await {
-            Some(rel) => rel,
-            None => return Err(ActionsError::ReleaseNotFound(tool.name.to_string())),
+    if let Some(file_path) = &tool.from_file {
+        let version = version.unwrap_or_else(|| parse_version(&tool.current_version));
+        if tool.current_version == version.as_tag() && env.is_tool_installed(tool, &version) {
+            info!("{}@{} is already installed", &tool.name, &version);
+            return Ok(());
+        }
+        return install_local_tool(env, tool, version, file_path).await;
+    }
+
+    let tool_source = source::for_tool(tool, system)?;
+
+    // without a version the tool is updated within its requirement, if it has one
+    let version = version.or_else(|| tool.version_req.as_deref().map(parse_version));
+    let (version, tag) = match version {
+        Some(Version::Requirement(requirement)) => {
+            let tag = resolve_requirement(&*tool_source, tool, &requirement).await?;
+            (parse_version(&tag), Some(tag))
+        }
+        Some(v) => (v, None),
+        None => match tool_source.resolve_version(&Version::Latest).await {
+            Ok(tag) => (parse_version(&tag), Some(tag)),
+            Err(_) => return Err(ActionsError::ReleaseNotFound(tool.name.to_string())),
         },
     };
 
-    if tool.current_version != version.as_tag() {
-        let release = github::get_specific_release_for_repo(owner, repo, &version).await?;
+    if tool.current_version == version.as_tag() && env.is_tool_installed(tool, &version) {
+        info!("{}@{} is already installed", &tool.name, &version);
+        return Ok(());
+    }
+
+    let tag = match tag {
+        Some(tag) => tag,
+        None => tool_source.resolve_version(&version).await?,
+    };
+    if let Some(toolchain) = tool_source.toolchain() {
+        return install_with_toolchain(env, tool, toolchain, version, &tag).await;
+    }
+    let artifacts = tool_source.list_artifacts(&tag).await?;
 
-        let asset = github::get_platform_specific_asset(&release, system, &tool.asset_pattern);
-        if asset.is_none() {
+    let asset = match tool_source.select_artifact(&artifacts, system, &tool.asset_pattern) {
+        Some(asset) => asset,
+        None => {
+            if let Some(fallback) = &tool.fallback {
+                info!(
+                    "No asset of {}@{} for {}, building it with {}",
+                    &tool.name,
+                    &version,
+                    system.platform(),
+                    fallback
+                );
+                let fallback_source = fallback_source(fallback, system)?;
+                let fallback_tag = fallback_source.resolve_version(&version).await?;
+                let toolchain = fallback_source
+                    .toolchain()
+                    .expect("fallback sources have a toolchain");
+                return install_with_toolchain(env, tool, toolchain, version, &fallback_tag).await;
+            }
             return Err(ActionsError::AssetNotFound {
                 tool_name: tool.name.to_string(),
                 version,
@@ -87,21 +425,278 @@ async fn handle_tool_install(
                 os: system.os.clone(),
             });
         }
-        let asset = asset.unwrap();
-        match env
-            .add_tool(
-                &tool.name,
-                &tool.alias,
-                version,
-                asset,
-                &tool.asset_pattern,
-                &tool.file_pattern,
-            )
-            .await
-        {
-            Ok(_) => (),
-            Err(add_tool_err) => return Err(add_tool_err.into()),
-        };
     };
+    let auth = tool_source.download_auth();
+    let asset_path = download::download_asset(
+        &asset.url,
+        &asset.name,
+        get_tool_version_dir(env, tool, &version),
+        &options.retry_policy(),
+        auth.as_ref(),
+    )
+    .await?;
+    verify_release_checksum(&artifacts, &asset, &asset_path, options, auth.as_ref()).await?;
+    verify_release_signatures(
+        &artifacts,
+        &asset,
+        &asset_path,
+        tool,
+        options,
+        auth.as_ref(),
+    )
+    .await?;
+    let locked_asset = lock_asset(system, &asset.name, asset.url.as_str(), &asset_path)?;
+
+    let version_tag = version.as_tag();
+    env.add_tool(tool, version, &asset_path).await?;
+    env.lock
+        .record(&tool.name, &version_tag, &tag, locked_asset);
+    Ok(())
+}
+
+/// The highest release of a tool satisfying a requirement, pre-releases only satisfy requirements
+/// that name one. Every page is fetched, releases are listed by date so maintenance releases of an
+/// older version can come before newer versions.
+async fn resolve_requirement(
+    tool_source: &'_ dyn source::ToolSource,
+    tool: &'_ Tool,
+    requirement: &'_ semver::VersionReq,
+) -> Result<String> {
+    let mut tags = Vec::new();
+    for page in 1.. {
+        let page_tags = tool_source.list_versions_page(true, page).await?;
+        if page_tags.is_empty() {
+            break;
+        }
+        tags.extend(page_tags);
+    }
+    version::highest_matching(requirement, &tags)
+        .map(ToString::to_string)
+        .ok_or_else(|| ActionsError::NoMatchingVersion {
+            tool_name: tool.name.to_string(),
+            requirement: requirement.to_string(),
+        })
+}
+
+/// The source named as a tool's fallback, it has to be one that builds tools with a toolchain.
+fn fallback_source(name: &'_ str, system: &'_ System) -> Result<Box<dyn source::ToolSource>> {
+    let fallback_source = source::for_tool(
+        &Tool {
+            name: name.to_string(),
+            ..Default::default()
+        },
+        system,
+    )?;
+    if fallback_source.toolchain().is_none() {
+        return Err(ActionsError::InvalidFallback(name.to_string()));
+    }
+    Ok(fallback_source)
+}
+
+/// Build a tool with its toolchain straight into the version directory, nothing is downloaded so
+/// the tool isn't recorded in the lock file.
+async fn install_with_toolchain(
+    env: &mut Environment,
+    tool: &'_ Tool,
+    toolchain: &'_ dyn Toolchain,
+    version: Version,
+    tag: &'_ str,
+) -> Result<()> {
+    if env.is_foreign() {
+        return Err(ActionsError::ForeignToolchain {
+            tool_name: tool.name.to_string(),
+            platform: env.platform.clone().unwrap_or_default(),
+        });
+    }
+    let tool_version_dir = get_tool_version_dir(env, tool, &version);
+    let bin_dir = toolchain
+        .install(tag, &tool_version_dir)
+        .await
+        .map_err(SourceError::from)?;
+    let entry_points = toolchain
+        .entry_points(tag, &tool_version_dir)
+        .await
+        .map_err(SourceError::from)?;
+    let tool = Tool {
+        entry_points,
+        ..tool.clone()
+    };
+    env.add_installed_tool(&tool, version, &bin_dir)?;
+    Ok(())
+}
+
+/// Install a tool from an archive or directory on disk, nothing is downloaded so the tool isn't
+/// recorded in the lock file.
+async fn install_local_tool(
+    env: &mut Environment,
+    tool: &'_ Tool,
+    version: Version,
+    file_path: &'_ Path,
+) -> Result<()> {
+    if !file_path.exists() {
+        return Err(ActionsError::LocalFileRead {
+            file_path: file_path.to_path_buf(),
+            source: std::io::ErrorKind::NotFound.into(),
+        });
+    }
+    if file_path.is_dir() {
+        env.add_tool_from_dir(tool, version, file_path).await?;
+    } else {
+        env.add_tool(tool, version, file_path).await?;
+    }
+    env.lock.remove(&tool.name);
+    Ok(())
+}
+
+/// Install a tool using exactly the asset recorded in the lock file, the download has to match
+/// the locked size and SHA-256 digest before it is extracted.
+async fn install_locked_tool(
+    env: &mut Environment,
+    tool: &'_ Tool,
+    system: &'_ System,
+    locked_asset: &'_ LockedAsset,
+    options: &'_ InstallOptions,
+) -> Result<()> {
+    let version = parse_version(&tool.current_version);
+    let url =
+        reqwest::Url::parse(&locked_asset.url).map_err(|parse_err| ActionsError::InvalidUrl {
+            tool_name: tool.name.to_string(),
+            url: locked_asset.url.to_string(),
+            message: parse_err.to_string(),
+        })?;
+    let tool_source = source::for_tool(tool, system)?;
+    let auth = tool_source.download_auth();
+    let asset_path = download::download_asset(
+        &url,
+        &locked_asset.name,
+        get_tool_version_dir(env, tool, &version),
+        &options.retry_policy(),
+        auth.as_ref(),
+    )
+    .await?;
+
+    let to_checksum_err = |source| ActionsError::Checksum {
+        file_path: asset_path.to_path_buf(),
+        source,
+    };
+    let size = std::fs::metadata(&asset_path)
+        .map_err(to_checksum_err)?
+        .len();
+    let sha256 = checksum::sha256_file(&asset_path).map_err(to_checksum_err)?;
+    let mismatch = if size != locked_asset.size {
+        Some(("size", locked_asset.size.to_string(), size.to_string()))
+    } else if sha256 != locked_asset.sha256 {
+        Some(("sha256", locked_asset.sha256.to_string(), sha256))
+    } else {
+        None
+    };
+    if let Some((field, expected, actual)) = mismatch {
+        // never leave an unverified asset behind where it could be picked up later
+        let _ = std::fs::remove_file(&asset_path);
+        return Err(ActionsError::LockMismatch {
+            tool_name: tool.name.to_string(),
+            asset_name: locked_asset.name.to_string(),
+            field,
+            expected,
+            actual,
+        });
+    }
+
+    if !tool.verify.is_empty() {
+        // signatures aren't part of the lock, they are verified against the locked release
+        let locked_tag = match env.lock.get_tool(&tool.name) {
+            Some(locked_tool) => locked_tool.tag.to_string(),
+            None => tool_source.resolve_version(&version).await?,
+        };
+        let artifacts = tool_source.list_artifacts(&locked_tag).await?;
+        match artifacts.iter().find(|a| a.name == locked_asset.name) {
+            Some(asset) => {
+                verify_release_signatures(
+                    &artifacts,
+                    asset,
+                    &asset_path,
+                    tool,
+                    options,
+                    auth.as_ref(),
+                )
+                .await?
+            }
+            None => {
+                return Err(ActionsError::AssetNotFound {
+                    tool_name: tool.name.to_string(),
+                    version,
+                    arch: system.architecture.clone(),
+                    os: system.os.clone(),
+                })
+            }
+        }
+    }
+
+    env.add_tool(tool, version, &asset_path).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        async_trait::async_trait,
+        source::{Artifact, ToolSource},
+        test_case::test_case,
+    };
+
+    /// Releases served two tags per page, newest first.
+    #[derive(Debug)]
+    struct PagedSource(Vec<&'static str>);
+
+    #[async_trait]
+    impl ToolSource for PagedSource {
+        fn default_alias(&self) -> &str {
+            "tool"
+        }
+
+        async fn list_versions(&self, pre_release: bool) -> source::Result<Vec<String>> {
+            self.list_versions_page(pre_release, 1).await
+        }
+
+        async fn list_versions_page(
+            &self,
+            _pre_release: bool,
+            page: u32,
+        ) -> source::Result<Vec<String>> {
+            Ok(self
+                .0
+                .chunks(2)
+                .nth(page as usize - 1)
+                .map(|tags| tags.iter().map(ToString::to_string).collect())
+                .unwrap_or_default())
+        }
+
+        async fn resolve_version(&self, version: &'_ Version) -> source::Result<String> {
+            Ok(version.as_tag())
+        }
+
+        async fn list_artifacts(&self, _tag: &'_ str) -> source::Result<Vec<Artifact>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test_case("^3", Some("v3.0.1") ; "first page")]
+    #[test_case("~1.5", Some("v1.5.0") ; "later page")]
+    #[test_case("^0.9", None ; "no page")]
+    #[test_case(">=1.4, <3", Some("v2.1.0") ; "maintenance release on an earlier page")]
+    #[test_case("~1.4", Some("v1.4.4") ; "maintenance release before older versions")]
+    fn resolve_requirement_tests(requirement: &'_ str, expected: Option<&'_ str>) {
+        // a maintenance release of 1.4 is listed before newer versions
+        let source = PagedSource(vec![
+            "v3.0.1", "v1.4.4", "v3.0.0", "v2.1.0", "v2.0.0", "v1.6.0", "v1.5.0", "v1.4.3",
+            "v1.4.2", "v1.3.1", "v1.3.0",
+        ]);
+        let resolved = async_std::task::block_on(resolve_requirement(
+            &source,
+            &Tool::default(),
+            &semver::VersionReq::parse(requirement).unwrap(),
+        ));
+        assert_eq!(resolved.ok(), expected.map(String::from))
+    }
+}
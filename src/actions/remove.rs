@@ -11,16 +11,19 @@ pub async fn remove_tool(
         info!("Removing {name} from environment. {}", &env.name);
         let env_path = Path::new(&env.base_dir);
 
-        let link_path = dirs::get_tool_link_path(env_path, &env_tool.name);
-        if link_path.exists() {
-            debug!("Removing symlink {:?}", &link_path);
-            if let Err(remove_err) = std::fs::remove_file(&link_path) {
-                return Err(super::ActionsError::FileDelete {
-                    file_name: link_path,
-                    symlink: true,
-                    source: remove_err,
-                });
-            };
+        for binary in env_tool.links() {
+            let link_path = dirs::get_tool_link_path(env_path, &binary.link);
+            // the links of binaries that weren't found are missing
+            if link_path.symlink_metadata().is_ok() {
+                debug!("Removing symlink {:?}", &link_path);
+                if let Err(remove_err) = std::fs::remove_file(&link_path) {
+                    return Err(super::ActionsError::FileDelete {
+                        file_name: link_path,
+                        symlink: true,
+                        source: remove_err,
+                    });
+                };
+            }
         }
 
         if remove_all_versions {
@@ -49,6 +52,7 @@ pub async fn remove_tool(
         let tool_idx = env.tools.iter().position(|t| t.name == name).unwrap();
         debug!("Found {} at index {}, removing...", name, tool_idx);
         env.tools.swap_remove(tool_idx);
+        env.lock.remove(name);
         Ok(())
     } else {
         // anyhow::bail!("{} is not found in the {} environment.", name, env.name)
@@ -1,21 +1,152 @@
-use crate::{environment::Environment, system::System, tool::Tool, version::parse_version};
+use crate::{
+    cli::InstallOptions,
+    environment::Environment,
+    lockfile::{LockedAsset, Lockfile},
+    source,
+    system::System,
+    tool::Tool,
+    version::parse_version,
+};
 use indicatif::ProgressBar;
 use tracing::{error, info};
 
-pub async fn sync_tools(env: &mut Environment, system: &'_ System) -> super::Result<()> {
+/// Install every tool of the environment strictly from the lock file. Tools missing from the lock
+/// or locked at another version fail unless `allow_unlocked` is set, then their release is resolved
+/// again and locked.
+pub async fn sync_tools(
+    env: &mut Environment,
+    system: &'_ System,
+    options: &'_ InstallOptions,
+    allow_unlocked: bool,
+) -> super::Result<()> {
     let tools: Vec<Tool> = env.tools.to_vec();
     let progress_bar = ProgressBar::new(tools.len() as u64);
+    let platform = system.platform();
 
+    let mut failed_tools = Vec::new();
     for tool in tools.iter() {
-        let parsed_version = parse_version(&tool.current_version);
-        match super::handle_tool_install(env, tool, system, Some(parsed_version)).await {
+        let install_result = sync_tool(env, tool, system, &platform, options, allow_unlocked).await;
+        match install_result {
             Ok(_) => info!(
                 "Tool {} has been installed at version {}",
                 &tool.name, tool.current_version
             ),
-            Err(install_err) => error!("Failed to install {}. {:?}", &tool.name, install_err),
+            Err(install_err) => {
+                error!("Failed to install {}. {}", &tool.name, install_err);
+                failed_tools.push(tool.name.to_string());
+            }
         }
         progress_bar.inc(1);
     }
-    Ok(())
+
+    if failed_tools.is_empty() {
+        Ok(())
+    } else {
+        Err(super::ActionsError::SyncFailed(failed_tools))
+    }
+}
+
+async fn sync_tool(
+    env: &mut Environment,
+    tool: &'_ Tool,
+    system: &'_ System,
+    platform: &'_ str,
+    options: &'_ InstallOptions,
+    allow_unlocked: bool,
+) -> super::Result<()> {
+    let parsed_version = parse_version(&tool.current_version);
+    // tools installed from a local file or built by a toolchain aren't downloaded, so they are
+    // never locked
+    if tool.from_file.is_some() || source::for_tool(tool, system)?.toolchain().is_some() {
+        if env.is_tool_installed(tool, &parsed_version) {
+            return Ok(());
+        }
+        return super::handle_tool_install(env, tool, system, Some(parsed_version), options).await;
+    }
+
+    match locked_asset(&env.lock, tool, platform) {
+        Ok(_) if env.is_tool_installed(tool, &parsed_version) => Ok(()),
+        Ok(locked_asset) => {
+            info!("Installing {} from the lock file", &tool.name);
+            super::install_locked_tool(env, tool, system, &locked_asset, options).await
+        }
+        Err(lock_err) if allow_unlocked => {
+            info!("{lock_err}");
+            // installed again even when it is linked, so the lock records what it resolves to
+            let unlocked_tool = Tool {
+                current_version: String::new(),
+                ..tool.clone()
+            };
+            super::handle_tool_install(env, &unlocked_tool, system, Some(parsed_version), options)
+                .await
+        }
+        Err(lock_err) => Err(lock_err),
+    }
+}
+
+/// The asset locked for a tool on a platform, the lock has to be for the version of the tool in the
+/// environment.
+fn locked_asset(
+    lock: &'_ Lockfile,
+    tool: &'_ Tool,
+    platform: &'_ str,
+) -> super::Result<LockedAsset> {
+    let not_locked = || super::ActionsError::NotLocked {
+        tool_name: tool.name.to_string(),
+        platform: platform.to_string(),
+    };
+    let locked_tool = lock.get_tool(&tool.name).ok_or_else(not_locked)?;
+    let version = parse_version(&tool.current_version).as_tag();
+    if locked_tool.version != version {
+        return Err(super::ActionsError::LockedVersionMismatch {
+            tool_name: tool.name.to_string(),
+            version,
+            locked_version: locked_tool.version.to_string(),
+        });
+    }
+    locked_tool
+        .assets
+        .iter()
+        .find(|asset| asset.platform == platform)
+        .cloned()
+        .ok_or_else(not_locked)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::actions::ActionsError, test_case::test_case};
+
+    #[test_case("cli/cli", "2.10.1", "linux/x86_64", Ok("gh_linux.tar.gz") ; "locked")]
+    #[test_case("cli/cli", "2.10.1", "macos/arm64", Err("not locked") ; "other platform")]
+    #[test_case("cli/cli", "2.9.0", "linux/x86_64", Err("version mismatch") ; "other version")]
+    #[test_case("BurntSushi/ripgrep", "13.0.0", "linux/x86_64", Err("not locked") ; "missing tool")]
+    fn locked_asset_tests(
+        name: &'_ str,
+        version: &'_ str,
+        platform: &'_ str,
+        expected: Result<&'_ str, &'_ str>,
+    ) {
+        let mut lock = Lockfile::default();
+        lock.record(
+            "cli/cli",
+            "2.10.1",
+            "v2.10.1",
+            LockedAsset {
+                platform: "linux/x86_64".to_string(),
+                name: "gh_linux.tar.gz".to_string(),
+                url: "https://example.com/gh_linux.tar.gz".to_string(),
+                size: 42,
+                sha256: "abc123".to_string(),
+            },
+        );
+        let tool = Tool::new(name, "tool", &parse_version(version), "", "");
+        let result = locked_asset(&lock, &tool, platform)
+            .map(|asset| asset.name)
+            .map_err(|lock_err| match lock_err {
+                ActionsError::NotLocked { .. } => "not locked",
+                ActionsError::LockedVersionMismatch { .. } => "version mismatch",
+                _ => "other",
+            });
+        assert_eq!(result, expected.map(String::from))
+    }
 }
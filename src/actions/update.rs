@@ -1,4 +1,10 @@
-use crate::{environment::Environment, system::System, tool::Tool, version::parse_version};
+use crate::{
+    cli::InstallOptions,
+    environment::Environment,
+    system::System,
+    tool::Tool,
+    version::{parse_version, Version},
+};
 use indicatif::{ProgressBar, ProgressStyle};
 use tracing::{error, info};
 
@@ -11,6 +17,7 @@ pub async fn update_tools(
     env: &mut Environment,
     system: &'_ System,
     update_type: UpdateType,
+    options: &'_ InstallOptions,
 ) -> super::Result<()> {
     match update_type {
         UpdateType::All => {
@@ -25,7 +32,7 @@ pub async fn update_tools(
             let mut failed_tools = Vec::new();
             for tool in tools {
                 progress_bar.set_message(tool.name.clone());
-                match super::handle_tool_install(env, &tool, system, None).await {
+                match super::handle_tool_install(env, &tool, system, None, options).await {
                     Ok(_) => info!("Tool {} complete.", &tool.name),
                     Err(install_err) => failed_tools.push(install_err.to_string()),
                 }
@@ -53,8 +60,16 @@ pub async fn update_tools(
                 .find(|t| t.name == split_name[0] || t.alias == split_name[0])
             {
                 info!("Updating: {:?}", tool);
+                // a requested version replaces the requirement of the tool
+                let mut tool = tool.clone();
+                if let Some(version) = &version {
+                    tool.version_req = match version {
+                        Version::Requirement(requirement) => Some(requirement.to_string()),
+                        _ => None,
+                    };
+                }
 
-                match super::handle_tool_install(env, tool, system, version).await {
+                match super::handle_tool_install(env, &tool, system, version, options).await {
                     Ok(_) => info!("{} has been updated.", &tool.name),
                     Err(install_err) => error!("{:?}", install_err),
                 }
@@ -0,0 +1,281 @@
+use super::{safety, ArchiveFormat, Archiver, ArchiverError};
+use async_trait::async_trait;
+use backhand::{FilesystemReader, InnerNode};
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+};
+use tracing::debug;
+
+/// The directory the image of an AppImage is extracted into, named like the one created by
+/// `--appimage-extract`.
+const APP_DIR: &str = "squashfs-root";
+/// Entry point of an extracted AppImage, relative to the directory it was extracted in. It sets
+/// up the environment of the bundled application before running it.
+pub const APP_IMAGE_ENTRY_POINT: &str = "squashfs-root/AppRun";
+
+/// Extracts the squashfs image appended to the runtime of an AppImage. The AppImage is never run,
+/// so neither FUSE nor the machine the AppImage was built for are needed.
+pub struct AppImageArchiver;
+
+#[async_trait]
+impl Archiver for AppImageArchiver {
+    async fn extract_to(&self, file_path: &'_ Path, out_dir: &'_ Path) -> super::Result<()> {
+        tracing::debug!(
+            "Extracting {} using the 'AppImage' Archiver",
+            file_path.display()
+        );
+        let to_io_err = |source| ArchiverError::Io {
+            file_path: file_path.to_path_buf(),
+            source,
+        };
+        let mut file = File::open(file_path).map_err(to_io_err)?;
+        let mut header = [0; 64];
+        file.read_exact(&mut header).map_err(to_io_err)?;
+        let offset = payload_offset(&header).ok_or_else(|| {
+            extractor_err(format!(
+                "'{}' does not start with an ELF runtime",
+                file_path.display()
+            ))
+        })?;
+        debug!("Image of '{}' starts at {offset}", file_path.display());
+
+        let app_dir = out_dir.join(APP_DIR);
+        if app_dir.exists() {
+            std::fs::remove_dir_all(&app_dir).map_err(|remove_err| ArchiverError::Io {
+                file_path: app_dir.to_path_buf(),
+                source: remove_err,
+            })?;
+        }
+        let image = FilesystemReader::from_reader_with_offset(BufReader::new(file), offset)
+            .map_err(|squashfs_err| {
+                extractor_err(format!(
+                    "The image of '{}' could not be read. {squashfs_err}",
+                    file_path.display()
+                ))
+            })?;
+        unpack(&image, &app_dir)?;
+        if !out_dir.join(APP_IMAGE_ENTRY_POINT).exists() {
+            return Err(extractor_err(format!(
+                "The image of '{}' has no AppRun",
+                file_path.display()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn extract(&self, file_path: &'_ Path) -> super::Result<()> {
+        self.extract_to(file_path, Path::new(".")).await
+    }
+
+    fn format(&self) -> ArchiveFormat {
+        ArchiveFormat::AppImage
+    }
+
+    fn name(&self) -> &'static str {
+        "appimage"
+    }
+}
+
+fn extractor_err(message: String) -> ArchiverError {
+    ArchiverError::ExtractorError {
+        extractor: "appimage".to_string(),
+        message,
+    }
+}
+
+/// Where the squashfs image starts, right after the section headers that end the ELF runtime.
+fn payload_offset(header: &'_ [u8; 64]) -> Option<u64> {
+    if !header.starts_with(b"\x7fELF") {
+        return None;
+    }
+    let little_endian = match header[5] {
+        1 => true,
+        2 => false,
+        _ => return None,
+    };
+    let read = |start: usize, len: usize| {
+        let bytes = &header[start..start + len];
+        let fold = |value: u64, byte: &u8| value << 8 | u64::from(*byte);
+        match little_endian {
+            true => bytes.iter().rev().fold(0, fold),
+            false => bytes.iter().fold(0, fold),
+        }
+    };
+    // e_shoff, e_shentsize and e_shnum of 32 and 64 bit headers
+    let (section_headers, entry_size, entries) = match header[4] {
+        1 => (read(0x20, 4), read(0x2e, 2), read(0x30, 2)),
+        2 => (read(0x28, 8), read(0x3a, 2), read(0x3c, 2)),
+        _ => return None,
+    };
+    section_headers.checked_add(entry_size * entries)
+}
+
+/// Unpack the files, directories and symbolic links of the image into `app_dir`.
+fn unpack(image: &'_ FilesystemReader, app_dir: &'_ Path) -> super::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let app_dir = safety::resolve_out_dir(app_dir)?;
+    for node in image.files() {
+        let relative_path = safety::enclosed_path(
+            "appimage",
+            node.fullpath.strip_prefix("/").unwrap_or(&node.fullpath),
+        )?;
+        // the root of the image
+        if relative_path.as_os_str().is_empty() {
+            continue;
+        }
+        let out_path = app_dir.join(&relative_path);
+        safety::check_destination("appimage", &app_dir, &out_path)?;
+        let to_io_err = |source| ArchiverError::Io {
+            file_path: out_path.to_path_buf(),
+            source,
+        };
+        let create_dir = |dir: &Path| {
+            std::fs::create_dir_all(dir).map_err(|create_err| {
+                ArchiverError::FailedToCreateDirectory {
+                    file_path: dir.to_path_buf(),
+                    source: create_err,
+                }
+            })
+        };
+        match &node.inner {
+            InnerNode::Dir(_) => create_dir(&out_path)?,
+            InnerNode::File(file) => {
+                if let Some(parent) = out_path.parent() {
+                    create_dir(parent)?;
+                }
+                safety::remove_existing_link(&out_path)?;
+                let mut out_file = File::create(&out_path).map_err(|create_err| {
+                    ArchiverError::FailedToCreateFile {
+                        file_path: out_path.to_path_buf(),
+                        source: create_err,
+                    }
+                })?;
+                io::copy(&mut image.file(&file.basic).reader(), &mut out_file)
+                    .map_err(to_io_err)?;
+                std::fs::set_permissions(
+                    &out_path,
+                    std::fs::Permissions::from_mode(u32::from(node.header.permissions) & 0o7777),
+                )
+                .map_err(to_io_err)?;
+            }
+            InnerNode::Symlink(symlink) => {
+                safety::check_link_target("appimage", &relative_path, &symlink.link)?;
+                if let Some(parent) = out_path.parent() {
+                    create_dir(parent)?;
+                }
+                safety::remove_existing_link(&out_path)?;
+                std::os::unix::fs::symlink(&symlink.link, &out_path).map_err(to_io_err)?;
+            }
+            _ => debug!("Skipping special file '{}'", out_path.display()),
+        }
+    }
+    Ok(())
+}
+
+/// AppImages mount their image with FUSE when they run, without it they have to be extracted.
+pub fn fuse_available() -> bool {
+    Path::new("/dev/fuse").exists()
+        && std::env::var_os("PATH").map_or(false, |path| {
+            std::env::split_paths(&path)
+                .any(|dir| dir.join("fusermount").is_file() || dir.join("fusermount3").is_file())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::TempDir,
+        backhand::{FilesystemWriter, NodeHeader},
+        std::io::Cursor,
+        test_case::test_case,
+    };
+
+    /// An ELF header of the AppImage runtime, its section headers start at `shoff`.
+    fn runtime_header(class: u8, little_endian: bool, shoff: u64) -> [u8; 64] {
+        let mut header = [0; 64];
+        header[..4].copy_from_slice(b"\x7fELF");
+        header[4] = class;
+        header[5] = if little_endian { 1 } else { 2 };
+        header[8..11].copy_from_slice(b"AI\x02");
+        let mut write = |start: usize, len: usize, value: u64| {
+            let bytes = match little_endian {
+                true => value.to_le_bytes()[..len].to_vec(),
+                false => value.to_be_bytes()[8 - len..].to_vec(),
+            };
+            header[start..start + len].copy_from_slice(&bytes);
+        };
+        match class {
+            1 => {
+                write(0x20, 4, shoff);
+                write(0x2e, 2, 40);
+                write(0x30, 2, 2);
+            }
+            _ => {
+                write(0x28, 8, shoff);
+                write(0x3a, 2, 64);
+                write(0x3c, 2, 1);
+            }
+        }
+        header
+    }
+
+    #[test_case(2, true, Some(64 + 64) ; "64 bit")]
+    #[test_case(2, false, Some(64 + 64) ; "64 bit big endian")]
+    #[test_case(1, true, Some(64 + 2 * 40) ; "32 bit")]
+    #[test_case(3, true, None ; "unknown class")]
+    fn payload_offset_tests(class: u8, little_endian: bool, expected: Option<u64>) {
+        assert_eq!(
+            payload_offset(&runtime_header(class, little_endian, 64)),
+            expected
+        )
+    }
+
+    #[test]
+    fn not_an_elf_file() {
+        assert_eq!(payload_offset(&[0; 64]), None)
+    }
+
+    #[test]
+    fn extract_image_without_running_it() {
+        // running the AppImage would leave a marker behind
+        let app_run = b"#!/bin/sh\ntouch ran\n";
+        let mut image = FilesystemWriter::default();
+        image
+            .push_file(&app_run[..], "AppRun", NodeHeader::new(0o755, 0, 0, 0))
+            .unwrap();
+        image
+            .push_dir_all("usr/bin", NodeHeader::new(0o755, 0, 0, 0))
+            .unwrap();
+        image
+            .push_file(
+                &b"tool"[..],
+                "usr/bin/tool",
+                NodeHeader::new(0o755, 0, 0, 0),
+            )
+            .unwrap();
+        image
+            .push_symlink("usr/bin/tool", "tool", NodeHeader::new(0o777, 0, 0, 0))
+            .unwrap();
+        let mut app_image = Cursor::new(runtime_header(2, true, 64).to_vec());
+        app_image.get_mut().resize(128, 0);
+        image.write_with_offset(&mut app_image, 128).unwrap();
+
+        let temp_dir = TempDir::new("appimage");
+        let base_dir = temp_dir.path();
+        let file_path = base_dir.join("Tool-x86_64.AppImage");
+        std::fs::write(&file_path, app_image.into_inner()).unwrap();
+        let out_dir = base_dir.join("out");
+        let result = async_std::task::block_on(AppImageArchiver.extract_to(&file_path, &out_dir));
+        let extracted = std::fs::read(out_dir.join(APP_IMAGE_ENTRY_POINT));
+        let linked = std::fs::read_link(out_dir.join(APP_DIR).join("tool"));
+        let ran = out_dir.join("ran").exists() || base_dir.join("ran").exists();
+        result.unwrap();
+        assert_eq!(extracted.unwrap(), app_run);
+        assert_eq!(linked.unwrap(), Path::new("usr/bin/tool"));
+        assert!(!ran);
+    }
+}
@@ -0,0 +1,66 @@
+use super::{ArchiveFormat, Archiver, ArchiverError, Compression};
+use async_trait::async_trait;
+use std::{fs::File, path::Path};
+
+/// Decompresses a single compressed file, usually the binary of a tool.
+pub struct CompressedArchiver(pub Compression);
+
+#[async_trait]
+impl Archiver for CompressedArchiver {
+    async fn extract_to(&self, file_path: &'_ Path, out_dir: &'_ Path) -> super::Result<()> {
+        tracing::debug!(
+            "Extracting {} using the '{}' Archiver",
+            file_path.display(),
+            self.name()
+        );
+        // files recognised by their contents don't always have an extension to strip
+        let file_name = file_path.file_name().unwrap().to_string_lossy();
+        let extension = format!(".{}", self.0.extension());
+        let out_file_name = if file_name.to_lowercase().ends_with(&extension) {
+            &file_name[..file_name.len() - extension.len()]
+        } else {
+            &file_name
+        };
+        let out_file_path = out_dir.join(out_file_name);
+        // decompress next to the output so the archive itself can be replaced
+        let part_file_path = out_dir.join(format!("{out_file_name}.part"));
+        let to_io_err = |file_path: &Path, source| ArchiverError::Io {
+            file_path: file_path.to_path_buf(),
+            source,
+        };
+
+        let mut decoder = File::open(file_path)
+            .and_then(|file| self.0.decoder(file))
+            .map_err(|open_err| to_io_err(file_path, open_err))?;
+        let mut out_file = File::create(&part_file_path).map_err(|create_err| {
+            ArchiverError::FailedToCreateFile {
+                file_path: part_file_path.to_path_buf(),
+                source: create_err,
+            }
+        })?;
+
+        std::io::copy(&mut decoder, &mut out_file)
+            .map_err(|copy_err| to_io_err(&part_file_path, copy_err))?;
+        std::fs::rename(&part_file_path, &out_file_path)
+            .map_err(|rename_err| to_io_err(&out_file_path, rename_err))?;
+
+        Ok(())
+    }
+
+    async fn extract(&self, file_path: &'_ Path) -> super::Result<()> {
+        self.extract_to(file_path, Path::new(".")).await
+    }
+
+    fn format(&self) -> ArchiveFormat {
+        ArchiveFormat::Compressed(self.0)
+    }
+
+    fn name(&self) -> &'static str {
+        match self.0 {
+            Compression::Gzip => "gzip",
+            Compression::Xz => "xz",
+            Compression::Bzip2 => "bzip2",
+            Compression::Zstd => "zstd",
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use super::{ArchiveFormat, Archiver, ArchiverError};
+use async_trait::async_trait;
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+};
+use tar::Archive;
+use tracing::debug;
+
+/// Debian packages are `ar` archives, the files are in their `data.tar.*` member.
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+const AR_HEADER_LEN: usize = 60;
+
+pub struct DebArchiver;
+
+#[async_trait]
+impl Archiver for DebArchiver {
+    async fn extract_to(&self, file_path: &'_ Path, out_dir: &'_ Path) -> super::Result<()> {
+        tracing::debug!(
+            "Extracting {} using the 'Deb' Archiver",
+            file_path.display()
+        );
+        let file = File::open(file_path).map_err(|open_err| ArchiverError::Io {
+            file_path: file_path.to_path_buf(),
+            source: open_err,
+        })?;
+        unpack(BufReader::new(file), file_path, out_dir)
+    }
+
+    async fn extract(&self, file_path: &'_ Path) -> super::Result<()> {
+        self.extract_to(file_path, Path::new(".")).await
+    }
+
+    fn format(&self) -> ArchiveFormat {
+        ArchiveFormat::Deb
+    }
+
+    fn name(&self) -> &'static str {
+        "deb"
+    }
+}
+
+fn extractor_err(message: String) -> ArchiverError {
+    ArchiverError::ExtractorError {
+        extractor: "deb".to_string(),
+        message,
+    }
+}
+
+/// Unpack the data of a package, the control files and maintainer scripts are skipped.
+fn unpack<R: Read>(mut reader: R, file_path: &'_ Path, out_dir: &'_ Path) -> super::Result<()> {
+    let to_io_err = |source| ArchiverError::Io {
+        file_path: file_path.to_path_buf(),
+        source,
+    };
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic).map_err(to_io_err)?;
+    if magic != AR_MAGIC {
+        return Err(extractor_err(format!(
+            "'{}' is not an ar archive",
+            file_path.display()
+        )));
+    }
+
+    loop {
+        let mut header = [0; AR_HEADER_LEN];
+        match reader.read_exact(&mut header) {
+            Ok(_) => (),
+            Err(read_err) if read_err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(read_err) => return Err(to_io_err(read_err)),
+        }
+        let name = String::from_utf8_lossy(&header[..16])
+            .trim_end()
+            .trim_end_matches('/')
+            .to_string();
+        let size: u64 = String::from_utf8_lossy(&header[48..58])
+            .trim()
+            .parse()
+            .map_err(|_| extractor_err(format!("Invalid size of member '{name}'")))?;
+        let mut member = (&mut reader).take(size);
+
+        if name.starts_with("data.tar") {
+            debug!("Extracting '{name}' from package '{}'", file_path.display());
+            return match ArchiveFormat::from_extension(Path::new(&name)) {
+                Some(ArchiveFormat::Tar) => {
+                    super::tar::unpack("deb", Archive::new(member), file_path, out_dir)
+                }
+                Some(ArchiveFormat::CompressedTar(compression)) => super::tar::unpack(
+                    "deb",
+                    Archive::new(compression.decoder(member).map_err(to_io_err)?),
+                    file_path,
+                    out_dir,
+                ),
+                _ => Err(extractor_err(format!("Unsupported data member '{name}'"))),
+            };
+        }
+
+        debug!("Skipping '{name}' of package '{}'", file_path.display());
+        io::copy(&mut member, &mut io::sink()).map_err(to_io_err)?;
+        // members are aligned to an even offset
+        if size % 2 == 1 {
+            io::copy(&mut (&mut reader).take(1), &mut io::sink()).map_err(to_io_err)?;
+        }
+    }
+
+    Err(extractor_err(format!(
+        "'{}' does not contain a data.tar member",
+        file_path.display()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::test_support::TempDir, std::io::Write};
+
+    fn ar_member(name: &'_ str, contents: &'_ [u8]) -> Vec<u8> {
+        let mut member = format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+            format!("{name}/"),
+            0,
+            0,
+            0,
+            100644,
+            contents.len()
+        )
+        .into_bytes();
+        member.extend_from_slice(contents);
+        if contents.len() % 2 == 1 {
+            member.push(b'\n');
+        }
+        member
+    }
+
+    #[test]
+    fn unpack_data_member() {
+        let mut data = tar::Builder::new(Vec::new());
+        let contents = b"#!/bin/sh\necho tool\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        data.append_data(&mut header, "./usr/bin/tool", &contents[..])
+            .unwrap();
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+        encoder.write_all(&data.into_inner().unwrap()).unwrap();
+
+        let mut deb = AR_MAGIC.to_vec();
+        deb.extend(ar_member("debian-binary", b"2.0\n"));
+        deb.extend(ar_member("control.tar.gz", b"not inspected"));
+        deb.extend(ar_member("data.tar.xz", &encoder.finish().unwrap()));
+
+        let out_dir = TempDir::new("deb");
+        unpack(&deb[..], Path::new("tool.deb"), out_dir.path()).unwrap();
+        let unpacked = std::fs::read(out_dir.path().join("usr/bin/tool"));
+        assert_eq!(unpacked.unwrap(), contents);
+    }
+
+    #[test]
+    fn missing_data_member() {
+        let mut deb = AR_MAGIC.to_vec();
+        deb.extend(ar_member("debian-binary", b"2.0\n"));
+        assert!(unpack(&deb[..], Path::new("tool.deb"), Path::new("unused")).is_err());
+    }
+}
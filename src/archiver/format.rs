@@ -0,0 +1,250 @@
+use std::{
+    io::{self, Read},
+    path::Path,
+};
+use tracing::debug;
+
+/// Length of a tar header block, the `ustar` magic sits at offset 257 of it.
+const TAR_BLOCK_LEN: u64 = 512;
+const TAR_MAGIC_OFFSET: usize = 257;
+
+/// Compressions used for single files and tar archives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Bzip2,
+    Zstd,
+}
+
+impl Compression {
+    pub(super) fn from_magic(header: &'_ [u8]) -> Option<Self> {
+        [
+            (&b"\x1f\x8b"[..], Compression::Gzip),
+            (&b"\xfd7zXZ\x00"[..], Compression::Xz),
+            (&b"BZh"[..], Compression::Bzip2),
+            (&b"\x28\xb5\x2f\xfd"[..], Compression::Zstd),
+        ]
+        .into_iter()
+        .find(|(magic, _)| header.starts_with(magic))
+        .map(|(_, compression)| compression)
+    }
+
+    /// Extension of files compressed with this compression, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Xz => "xz",
+            Compression::Bzip2 => "bz2",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// Decompress a stream, concatenated streams are decompressed as a single one.
+    pub fn decoder<'a, R: Read + 'a>(&self, reader: R) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        })
+    }
+}
+
+/// The archive formats vers can extract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Tar,
+    CompressedTar(Compression),
+    Zip,
+    /// A single compressed file, usually the binary of a tool.
+    Compressed(Compression),
+    /// A Debian package, only its data archive is extracted.
+    Deb,
+    /// An RPM package, only its payload is extracted.
+    Rpm,
+    /// A type 2 AppImage, an executable with the application's squashfs image appended.
+    AppImage,
+}
+
+impl ArchiveFormat {
+    /// Recognise the format from the contents of a file.
+    pub fn from_contents(file_path: &'_ Path) -> Option<Self> {
+        Self::from_reader(|| std::fs::File::open(file_path)).unwrap_or_else(|read_err| {
+            debug!("Unable to read '{}'. {read_err}", file_path.display());
+            None
+        })
+    }
+
+    /// Recognise the format from the first bytes of a stream, `open` is called again to look
+    /// inside compressed streams since some compressions only decode whole blocks.
+    fn from_reader<R: Read>(open: impl Fn() -> io::Result<R>) -> io::Result<Option<Self>> {
+        let mut header = Vec::new();
+        open()?.take(TAR_BLOCK_LEN).read_to_end(&mut header)?;
+        if let Some(compression) = Compression::from_magic(&header) {
+            let mut decompressed = Vec::new();
+            // streams that can't be decompressed fail later, during the extraction
+            let _ = compression
+                .decoder(open()?)?
+                .take(TAR_BLOCK_LEN)
+                .read_to_end(&mut decompressed);
+            return Ok(Some(if is_tar(&decompressed) {
+                ArchiveFormat::CompressedTar(compression)
+            } else {
+                ArchiveFormat::Compressed(compression)
+            }));
+        }
+        // AppImages mark their ELF header with `AI` and the AppImage type, in the padding of e_ident
+        if header.starts_with(b"\x7fELF") && header.get(8..11) == Some(&b"AI\x02"[..]) {
+            return Ok(Some(ArchiveFormat::AppImage));
+        }
+        if header.starts_with(b"!<arch>\ndebian") {
+            return Ok(Some(ArchiveFormat::Deb));
+        }
+        if header.starts_with(super::rpm::RPM_MAGIC) {
+            return Ok(Some(ArchiveFormat::Rpm));
+        }
+        if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+            return Ok(Some(ArchiveFormat::Zip));
+        }
+        if is_tar(&header) {
+            return Ok(Some(ArchiveFormat::Tar));
+        }
+        Ok(None)
+    }
+
+    /// Guess the format from the name of a file.
+    pub fn from_extension(file_path: &'_ Path) -> Option<Self> {
+        let file_name = file_path.file_name()?.to_str()?.to_lowercase();
+        [
+            (".tar.gz", ArchiveFormat::CompressedTar(Compression::Gzip)),
+            (".tgz", ArchiveFormat::CompressedTar(Compression::Gzip)),
+            (".tar.xz", ArchiveFormat::CompressedTar(Compression::Xz)),
+            (".txz", ArchiveFormat::CompressedTar(Compression::Xz)),
+            (".tar.bz2", ArchiveFormat::CompressedTar(Compression::Bzip2)),
+            (".tbz", ArchiveFormat::CompressedTar(Compression::Bzip2)),
+            (".tbz2", ArchiveFormat::CompressedTar(Compression::Bzip2)),
+            (".tar.zst", ArchiveFormat::CompressedTar(Compression::Zstd)),
+            (".tzst", ArchiveFormat::CompressedTar(Compression::Zstd)),
+            (".tar", ArchiveFormat::Tar),
+            (".zip", ArchiveFormat::Zip),
+            (".deb", ArchiveFormat::Deb),
+            (".rpm", ArchiveFormat::Rpm),
+            (".appimage", ArchiveFormat::AppImage),
+            (".gz", ArchiveFormat::Compressed(Compression::Gzip)),
+            (".xz", ArchiveFormat::Compressed(Compression::Xz)),
+            (".bz2", ArchiveFormat::Compressed(Compression::Bzip2)),
+            (".zst", ArchiveFormat::Compressed(Compression::Zstd)),
+        ]
+        .into_iter()
+        .find(|(extension, _)| file_name.ends_with(extension))
+        .map(|(_, format)| format)
+    }
+}
+
+/// POSIX and GNU tar headers carry `ustar` at a fixed offset, old v7 archives are only recognised
+/// by their extension.
+fn is_tar(header: &'_ [u8]) -> bool {
+    header
+        .get(TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 5)
+        .map_or(false, |magic| magic == b"ustar")
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::io::Write, test_case::test_case};
+
+    fn tar_bytes() -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let contents = b"#!/bin/sh\necho tool\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "tool", &contents[..])
+            .unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    fn compress(compression: Compression, contents: &'_ [u8]) -> Vec<u8> {
+        match compression {
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(contents).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(contents).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Bzip2 => {
+                let mut encoder =
+                    bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(contents).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Zstd => zstd::stream::encode_all(contents, 0).unwrap(),
+        }
+    }
+
+    fn detect(contents: &'_ [u8]) -> Option<ArchiveFormat> {
+        ArchiveFormat::from_reader(|| Ok(contents)).unwrap()
+    }
+
+    #[test]
+    fn tar_from_contents() {
+        assert_eq!(detect(&tar_bytes()), Some(ArchiveFormat::Tar))
+    }
+
+    #[test_case(Compression::Gzip ; "gzip")]
+    #[test_case(Compression::Xz ; "xz")]
+    #[test_case(Compression::Bzip2 ; "bzip2")]
+    #[test_case(Compression::Zstd ; "zstd")]
+    fn compressed_from_contents(compression: Compression) {
+        assert_eq!(
+            detect(&compress(compression, &tar_bytes())),
+            Some(ArchiveFormat::CompressedTar(compression))
+        );
+        assert_eq!(
+            detect(&compress(compression, b"\x7fELF\x02\x01\x01")),
+            Some(ArchiveFormat::Compressed(compression))
+        );
+    }
+
+    #[test_case(b"PK\x03\x04\x14\x00", Some(ArchiveFormat::Zip) ; "zip")]
+    #[test_case(b"!<arch>\ndebian-binary   ", Some(ArchiveFormat::Deb) ; "deb")]
+    #[test_case(b"!<arch>\nlibtool.o/      ", None ; "static library")]
+    #[test_case(b"\xed\xab\xee\xdb\x03\x00", Some(ArchiveFormat::Rpm) ; "rpm")]
+    #[test_case(b"\x7fELF\x02\x01\x01\x00AI\x02\x00", Some(ArchiveFormat::AppImage) ; "appimage")]
+    #[test_case(b"\x7fELF\x02\x01\x01\x00AI\x01\x00", None ; "appimage type 1")]
+    #[test_case(b"\x7fELF\x02\x01\x01", None ; "elf")]
+    #[test_case(b"", None ; "empty")]
+    fn from_contents_tests(contents: &'_ [u8], expected: Option<ArchiveFormat>) {
+        assert_eq!(detect(contents), expected)
+    }
+
+    #[test_case("tool_1.0.0_linux_amd64.tar.gz", Some(ArchiveFormat::CompressedTar(Compression::Gzip)) ; "tar gzip")]
+    #[test_case("tool-linux.TGZ", Some(ArchiveFormat::CompressedTar(Compression::Gzip)) ; "tgz upper case")]
+    #[test_case("nvim-linux64.tar.xz", Some(ArchiveFormat::CompressedTar(Compression::Xz)) ; "tar xz")]
+    #[test_case("tool.tbz", Some(ArchiveFormat::CompressedTar(Compression::Bzip2)) ; "tbz")]
+    #[test_case("tool.tar.zst", Some(ArchiveFormat::CompressedTar(Compression::Zstd)) ; "tar zstd")]
+    #[test_case("tool.tar", Some(ArchiveFormat::Tar) ; "tar")]
+    #[test_case("tool_windows.zip", Some(ArchiveFormat::Zip) ; "zip")]
+    #[test_case("tool.gz", Some(ArchiveFormat::Compressed(Compression::Gzip)) ; "gzip")]
+    #[test_case("shellcheck.xz", Some(ArchiveFormat::Compressed(Compression::Xz)) ; "xz")]
+    #[test_case("ripgrep_14.1.0-1_amd64.deb", Some(ArchiveFormat::Deb) ; "deb")]
+    #[test_case("tool-1.0.0-1.x86_64.rpm", Some(ArchiveFormat::Rpm) ; "rpm")]
+    #[test_case("nvim.appimage", Some(ArchiveFormat::AppImage) ; "appimage")]
+    #[test_case("Tool-x86_64.AppImage", Some(ArchiveFormat::AppImage) ; "appimage mixed case")]
+    #[test_case("nvim.appimage.zsync", None ; "appimage zsync")]
+    #[test_case("tool_linux", None ; "no extension")]
+    fn from_extension_tests(file_name: &'_ str, expected: Option<ArchiveFormat>) {
+        assert_eq!(
+            ArchiveFormat::from_extension(Path::new(file_name)),
+            expected
+        )
+    }
+}
@@ -1,11 +1,24 @@
-use self::{gzip::GzipArchiver, tar::TarArchiver, tar_gzip::TarGzipArchiver, zip::ZipArchiver};
+use self::{
+    appimage::AppImageArchiver, compressed::CompressedArchiver, deb::DebArchiver, rpm::RpmArchiver,
+    tar::TarArchiver, tar_compressed::TarCompressedArchiver, zip::ZipArchiver,
+};
+pub use self::{
+    appimage::{fuse_available, APP_IMAGE_ENTRY_POINT},
+    format::{ArchiveFormat, Compression},
+};
 use async_trait::async_trait;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
+use tracing::debug;
 
-mod gzip;
+mod appimage;
+mod compressed;
+mod deb;
+mod format;
+mod rpm;
+mod safety;
 mod tar;
-mod tar_gzip;
+mod tar_compressed;
 mod zip;
 
 #[derive(Debug, Error)]
@@ -28,6 +41,12 @@ pub enum ArchiverError {
         file_path: PathBuf,
         source: std::io::Error,
     },
+    #[error("Extractor({extractor}) refused to extract '{entry}', {reason}")]
+    UnsafeEntry {
+        extractor: String,
+        entry: PathBuf,
+        reason: String,
+    },
     #[error("Failed to create file {file_path}. {source:?}")]
     FailedToCreateFile {
         file_path: PathBuf,
@@ -41,22 +60,49 @@ type Result<T, E = ArchiverError> = std::result::Result<T, E>;
 pub trait Archiver {
     async fn extract_to(&self, file_path: &'_ Path, out_dir: &'_ Path) -> Result<()>;
     async fn extract(&self, file_path: &'_ Path) -> Result<()>;
-    fn can_handle(&self, file_path: &'_ Path) -> bool;
+    /// The format of the files this archiver extracts.
+    fn format(&self) -> ArchiveFormat;
     fn name(&self) -> &'static str;
 }
 
-pub fn determine_possible_extractors(file_path: &'_ Path) -> Vec<Box<dyn Archiver>> {
-    let possible_extractors: Vec<Box<dyn Archiver>> = vec![
-        Box::new(TarGzipArchiver),
-        Box::new(TarArchiver),
-        Box::new(ZipArchiver),
-        Box::new(GzipArchiver),
-    ];
+fn archiver_for(format: ArchiveFormat) -> Box<dyn Archiver> {
+    match format {
+        ArchiveFormat::Tar => Box::new(TarArchiver),
+        ArchiveFormat::CompressedTar(compression) => Box::new(TarCompressedArchiver(compression)),
+        ArchiveFormat::Zip => Box::new(ZipArchiver),
+        ArchiveFormat::Compressed(compression) => Box::new(CompressedArchiver(compression)),
+        ArchiveFormat::Deb => Box::new(DebArchiver),
+        ArchiveFormat::Rpm => Box::new(RpmArchiver),
+        ArchiveFormat::AppImage => Box::new(AppImageArchiver),
+    }
+}
 
-    possible_extractors
-        .into_iter()
-        .filter(|e| e.can_handle(file_path))
-        .collect()
+/// The archivers to try for a file, in order. The contents of the file decide its format, the
+/// extension is only a hint used when the contents aren't recognised or the extraction fails.
+pub fn determine_possible_extractors(file_path: &'_ Path) -> Vec<Box<dyn Archiver>> {
+    let detected = ArchiveFormat::from_contents(file_path);
+    let hinted = ArchiveFormat::from_extension(file_path);
+    debug!(
+        "Format of '{}' from its contents: {detected:?}, from its extension: {hinted:?}",
+        file_path.display()
+    );
+    let mut formats = match (detected, hinted) {
+        // tar archives without the ustar magic look like any other compressed file
+        (
+            Some(ArchiveFormat::Compressed(compression)),
+            Some(ArchiveFormat::CompressedTar(hinted)),
+        ) if compression == hinted => vec![
+            ArchiveFormat::CompressedTar(compression),
+            ArchiveFormat::Compressed(compression),
+        ],
+        (Some(detected), Some(hinted)) if detected != hinted => vec![detected, hinted],
+        (detected, hinted) => detected.or(hinted).into_iter().collect(),
+    };
+    // AppImages that can mount themselves are linked as they are
+    if fuse_available() {
+        formats.retain(|format| *format != ArchiveFormat::AppImage);
+    }
+    formats.into_iter().map(archiver_for).collect()
 }
 
 pub async fn handle_file_extraction(
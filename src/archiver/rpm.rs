@@ -0,0 +1,294 @@
+use super::{safety, ArchiveFormat, Archiver, ArchiverError, Compression};
+use async_trait::async_trait;
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+};
+use tracing::debug;
+
+pub(super) const RPM_MAGIC: &[u8] = &[0xed, 0xab, 0xee, 0xdb];
+const LEAD_LEN: usize = 96;
+const HEADER_MAGIC: &[u8] = &[0x8e, 0xad, 0xe8];
+const CPIO_HEADER_LEN: usize = 110;
+const CPIO_TRAILER: &str = "TRAILER!!!";
+
+pub struct RpmArchiver;
+
+#[async_trait]
+impl Archiver for RpmArchiver {
+    async fn extract_to(&self, file_path: &'_ Path, out_dir: &'_ Path) -> super::Result<()> {
+        tracing::debug!(
+            "Extracting {} using the 'Rpm' Archiver",
+            file_path.display()
+        );
+        let file = File::open(file_path).map_err(|open_err| ArchiverError::Io {
+            file_path: file_path.to_path_buf(),
+            source: open_err,
+        })?;
+        unpack(BufReader::new(file), file_path, out_dir)
+    }
+
+    async fn extract(&self, file_path: &'_ Path) -> super::Result<()> {
+        self.extract_to(file_path, Path::new(".")).await
+    }
+
+    fn format(&self) -> ArchiveFormat {
+        ArchiveFormat::Rpm
+    }
+
+    fn name(&self) -> &'static str {
+        "rpm"
+    }
+}
+
+fn extractor_err(message: String) -> ArchiverError {
+    ArchiverError::ExtractorError {
+        extractor: "rpm".to_string(),
+        message,
+    }
+}
+
+fn skip<R: Read>(reader: &mut R, len: u64) -> io::Result<()> {
+    io::copy(&mut reader.take(len), &mut io::sink()).map(|_| ())
+}
+
+/// Skip a header structure of the package, the signature header is padded to 8 bytes.
+fn skip_header<R: Read>(reader: &mut R, padded: bool) -> super::Result<()> {
+    let mut intro = [0; 16];
+    reader
+        .read_exact(&mut intro)
+        .map_err(|read_err| extractor_err(format!("Unable to read header. {read_err}")))?;
+    if !intro.starts_with(HEADER_MAGIC) {
+        return Err(extractor_err("Invalid header magic".to_string()));
+    }
+    let index_entries = u32::from_be_bytes([intro[8], intro[9], intro[10], intro[11]]) as u64;
+    let data_len = u32::from_be_bytes([intro[12], intro[13], intro[14], intro[15]]) as u64;
+    let len = index_entries * 16 + data_len;
+    let padding = if padded { (8 - len % 8) % 8 } else { 0 };
+    skip(reader, len + padding)
+        .map_err(|read_err| extractor_err(format!("Unable to read header. {read_err}")))
+}
+
+/// Unpack the payload of a package, a cpio archive compressed with the compression of the
+/// distribution that built it.
+fn unpack<R: Read>(mut reader: R, file_path: &'_ Path, out_dir: &'_ Path) -> super::Result<()> {
+    let to_io_err = |source| ArchiverError::Io {
+        file_path: file_path.to_path_buf(),
+        source,
+    };
+    let mut lead = [0; LEAD_LEN];
+    reader.read_exact(&mut lead).map_err(to_io_err)?;
+    if !lead.starts_with(RPM_MAGIC) {
+        return Err(extractor_err(format!(
+            "'{}' is not an rpm package",
+            file_path.display()
+        )));
+    }
+    skip_header(&mut reader, true)?;
+    skip_header(&mut reader, false)?;
+
+    let mut magic = [0; 6];
+    reader.read_exact(&mut magic).map_err(to_io_err)?;
+    let payload = io::Cursor::new(magic).chain(reader);
+    match Compression::from_magic(&magic) {
+        Some(compression) => unpack_cpio(
+            compression.decoder(payload).map_err(to_io_err)?,
+            file_path,
+            out_dir,
+        ),
+        None => unpack_cpio(payload, file_path, out_dir),
+    }
+}
+
+/// Unpack the files, directories and symbolic links of a `newc` cpio archive.
+fn unpack_cpio<R: Read>(
+    mut reader: R,
+    file_path: &'_ Path,
+    out_dir: &'_ Path,
+) -> super::Result<()> {
+    let to_io_err = |file_path: &Path, source| ArchiverError::Io {
+        file_path: file_path.to_path_buf(),
+        source,
+    };
+    let out_dir = safety::resolve_out_dir(out_dir)?;
+    loop {
+        let mut header = [0; CPIO_HEADER_LEN];
+        reader
+            .read_exact(&mut header)
+            .map_err(|read_err| to_io_err(file_path, read_err))?;
+        if !header.starts_with(b"070701") && !header.starts_with(b"070702") {
+            return Err(extractor_err(
+                "Payload is not a newc cpio archive".to_string(),
+            ));
+        }
+        let field = |index: usize| {
+            let start = 6 + index * 8;
+            std::str::from_utf8(&header[start..start + 8])
+                .ok()
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| extractor_err("Invalid cpio header".to_string()))
+        };
+        let (mode, file_size, name_size) = (field(1)?, field(6)? as u64, field(11)? as usize);
+
+        let mut name = vec![0; name_size];
+        reader
+            .read_exact(&mut name)
+            .map_err(|read_err| to_io_err(file_path, read_err))?;
+        let name = String::from_utf8_lossy(&name)
+            .trim_end_matches('\0')
+            .to_string();
+        skip(
+            &mut reader,
+            ((4 - (CPIO_HEADER_LEN + name_size) % 4) % 4) as u64,
+        )
+        .map_err(|read_err| to_io_err(file_path, read_err))?;
+        if name == CPIO_TRAILER {
+            return Ok(());
+        }
+
+        let relative_path = safety::enclosed_path("rpm", Path::new(&name))?;
+        let out_path = out_dir.join(&relative_path);
+        safety::check_destination("rpm", &out_dir, &out_path)?;
+        debug!(
+            "Extracting '{}' from package '{}'",
+            out_path.display(),
+            file_path.display()
+        );
+        let mut data = (&mut reader).take(file_size);
+        unpack_entry(mode, &mut data, &relative_path, &out_path)?;
+        skip(&mut data, u64::MAX).map_err(|read_err| to_io_err(file_path, read_err))?;
+        skip(&mut reader, (4 - file_size % 4) % 4)
+            .map_err(|read_err| to_io_err(file_path, read_err))?;
+    }
+}
+
+fn unpack_entry<R: Read>(
+    mode: u32,
+    data: &mut R,
+    relative_path: &'_ Path,
+    out_path: &'_ Path,
+) -> super::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let create_dir = |dir: &Path| {
+        std::fs::create_dir_all(dir).map_err(|create_err| ArchiverError::FailedToCreateDirectory {
+            file_path: dir.to_path_buf(),
+            source: create_err,
+        })
+    };
+    let to_io_err = |source| ArchiverError::Io {
+        file_path: out_path.to_path_buf(),
+        source,
+    };
+    match mode & 0o170000 {
+        0o040000 => create_dir(out_path),
+        0o100000 => {
+            if let Some(parent) = out_path.parent() {
+                create_dir(parent)?;
+            }
+            safety::remove_existing_link(out_path)?;
+            let mut out_file =
+                File::create(out_path).map_err(|create_err| ArchiverError::FailedToCreateFile {
+                    file_path: out_path.to_path_buf(),
+                    source: create_err,
+                })?;
+            io::copy(data, &mut out_file).map_err(to_io_err)?;
+            std::fs::set_permissions(out_path, std::fs::Permissions::from_mode(mode & 0o7777))
+                .map_err(to_io_err)
+        }
+        0o120000 => {
+            let mut target = String::new();
+            data.read_to_string(&mut target).map_err(to_io_err)?;
+            safety::check_link_target("rpm", relative_path, Path::new(&target))?;
+            if let Some(parent) = out_path.parent() {
+                create_dir(parent)?;
+            }
+            safety::remove_existing_link(out_path)?;
+            std::os::unix::fs::symlink(&target, out_path).map_err(to_io_err)
+        }
+        _ => {
+            debug!("Skipping special file '{}'", out_path.display());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::test_support::TempDir, std::io::Write, test_case::test_case};
+
+    fn cpio_entry(name: &'_ str, mode: u32, contents: &'_ [u8]) -> Vec<u8> {
+        let name_size = name.len() + 1;
+        let mut entry = format!(
+            "070701{:08x}{mode:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{name_size:08x}{:08x}",
+            1, 0, 0, 1, 0, contents.len(), 0, 0, 0, 0, 0
+        )
+        .into_bytes();
+        entry.extend_from_slice(name.as_bytes());
+        entry.push(0);
+        entry.resize(entry.len() + (4 - (CPIO_HEADER_LEN + name_size) % 4) % 4, 0);
+        entry.extend_from_slice(contents);
+        entry.resize(entry.len() + (4 - contents.len() % 4) % 4, 0);
+        entry
+    }
+
+    fn header_structure() -> Vec<u8> {
+        let mut header = HEADER_MAGIC.to_vec();
+        header.extend([1, 0, 0, 0, 0]);
+        header.extend(1u32.to_be_bytes());
+        header.extend(4u32.to_be_bytes());
+        header.extend([0; 16]);
+        header.extend(b"tag\0");
+        header
+    }
+
+    #[test]
+    fn unpack_payload() {
+        let contents = b"#!/bin/sh\necho tool\n";
+        let mut cpio = cpio_entry("./usr", 0o040755, b"");
+        cpio.extend(cpio_entry("./usr/bin/tool", 0o100755, contents));
+        cpio.extend(cpio_entry("./usr/bin/tool-link", 0o120777, b"tool"));
+        cpio.extend(cpio_entry(CPIO_TRAILER, 0, b""));
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&cpio).unwrap();
+
+        let mut rpm = RPM_MAGIC.to_vec();
+        rpm.resize(LEAD_LEN, 0);
+        // the signature header is 36 bytes long, padded to 40
+        rpm.extend(header_structure());
+        rpm.extend([0; 4]);
+        rpm.extend(header_structure());
+        rpm.extend(encoder.finish().unwrap());
+
+        let out_dir = TempDir::new("rpm");
+        unpack(&rpm[..], Path::new("tool.rpm"), &out_dir.path().join("out")).unwrap();
+        let unpacked = std::fs::read(out_dir.path().join("out/usr/bin/tool-link"));
+        assert_eq!(unpacked.unwrap(), contents);
+    }
+
+    #[test_case("parent", &[("./usr/../../escaped", 0o100755, "")] ; "parent directory")]
+    #[test_case("absolute-link", &[("./usr/bin/tool", 0o120777, "/bin/sh")] ; "absolute symlink")]
+    #[test_case("link", &[("./usr/bin/tool", 0o120777, "../../../escaped")] ; "symlink outside")]
+    #[test_case("through-link", &[
+        ("./current", 0o120777, "."),
+        ("./parent", 0o120777, "current/.."),
+        ("./parent/escaped", 0o100755, ""),
+    ] ; "write through symlinks")]
+    fn refuse_unsafe_entries(test_name: &'_ str, entries: &'_ [(&str, u32, &str)]) {
+        let mut cpio = Vec::new();
+        for (name, mode, contents) in entries {
+            cpio.extend(cpio_entry(name, *mode, contents.as_bytes()));
+        }
+        cpio.extend(cpio_entry(CPIO_TRAILER, 0, b""));
+        let base_dir = TempDir::new(&format!("rpm-{test_name}"));
+        let result = unpack_cpio(
+            &cpio[..],
+            Path::new("tool.rpm"),
+            &base_dir.path().join("out"),
+        );
+        let escaped = base_dir.path().join("escaped").exists();
+        assert!(matches!(result, Err(ArchiverError::UnsafeEntry { .. })));
+        assert!(!escaped);
+    }
+}
@@ -0,0 +1,138 @@
+use super::ArchiverError;
+use std::path::{Component, Path, PathBuf};
+
+fn unsafe_entry(extractor: &'_ str, entry: &'_ Path, reason: &'_ str) -> ArchiverError {
+    ArchiverError::UnsafeEntry {
+        extractor: extractor.to_string(),
+        entry: entry.to_path_buf(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Create the directory entries are extracted into and resolve it, so the destinations of entries
+/// can be compared with it.
+pub(super) fn resolve_out_dir(out_dir: &'_ Path) -> super::Result<PathBuf> {
+    let to_create_err = |source| ArchiverError::FailedToCreateDirectory {
+        file_path: out_dir.to_path_buf(),
+        source,
+    };
+    std::fs::create_dir_all(out_dir).map_err(to_create_err)?;
+    out_dir.canonicalize().map_err(to_create_err)
+}
+
+/// The path of an entry relative to the output directory. Absolute paths and paths with `..` are
+/// refused, `./` prefixes are dropped.
+pub(super) fn enclosed_path(extractor: &'_ str, entry: &'_ Path) -> super::Result<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in entry.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => (),
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(unsafe_entry(extractor, entry, "its path is absolute"))
+            }
+            Component::ParentDir => {
+                return Err(unsafe_entry(extractor, entry, "its path contains '..'"))
+            }
+        }
+    }
+    Ok(path)
+}
+
+/// Refuse symbolic links whose target, resolved from the directory of the link, is outside of the
+/// output directory. `entry` is the enclosed path of the link.
+pub(super) fn check_link_target(
+    extractor: &'_ str,
+    entry: &'_ Path,
+    target: &'_ Path,
+) -> super::Result<()> {
+    if target.has_root() {
+        return Err(unsafe_entry(
+            extractor,
+            entry,
+            "it links to an absolute path",
+        ));
+    }
+    let mut resolved = entry.parent().map(Path::to_path_buf).unwrap_or_default();
+    for component in target.components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::ParentDir if resolved.pop() => (),
+            Component::CurDir => (),
+            _ => {
+                return Err(unsafe_entry(
+                    extractor,
+                    entry,
+                    "it links outside of the output directory",
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Refuse destinations that are outside of the output directory once the links extracted before
+/// them are followed. `out_dir` has to be resolved with [resolve_out_dir].
+pub(super) fn check_destination(
+    extractor: &'_ str,
+    out_dir: &'_ Path,
+    destination: &'_ Path,
+) -> super::Result<()> {
+    // archives of packages start with an entry for `./`
+    if destination == out_dir {
+        return Ok(());
+    }
+    // entries create their missing parents, the closest existing one decides where they end up
+    let existing_parent = destination
+        .ancestors()
+        .skip(1)
+        .find(|ancestor| ancestor.exists())
+        .and_then(|ancestor| ancestor.canonicalize().ok());
+    match existing_parent {
+        Some(parent) if parent.starts_with(out_dir) => Ok(()),
+        _ => Err(unsafe_entry(
+            extractor,
+            destination,
+            "it would be written outside of the output directory through a link",
+        )),
+    }
+}
+
+/// Files are written to new links instead of through existing ones.
+pub(super) fn remove_existing_link(destination: &'_ Path) -> super::Result<()> {
+    if std::fs::symlink_metadata(destination).is_ok_and(|metadata| metadata.is_symlink()) {
+        std::fs::remove_file(destination).map_err(|source| ArchiverError::Io {
+            file_path: destination.to_path_buf(),
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    #[test_case("./usr/bin/tool", Some("usr/bin/tool") ; "relative")]
+    #[test_case("tool", Some("tool") ; "file name")]
+    #[test_case("/usr/bin/tool", None ; "absolute")]
+    #[test_case("usr/../../etc/passwd", None ; "parent directory")]
+    fn enclosed_path_tests(entry: &'_ str, expected: Option<&'_ str>) {
+        assert_eq!(
+            enclosed_path("test", Path::new(entry)).ok(),
+            expected.map(PathBuf::from)
+        )
+    }
+
+    #[test_case("usr/bin/tool", "../lib/tool/tool", true ; "sibling directory")]
+    #[test_case("usr/bin/tool-link", "tool", true ; "same directory")]
+    #[test_case("tool", "/bin/sh", false ; "absolute")]
+    #[test_case("usr/bin/tool", "../../../etc/passwd", false ; "parent of output directory")]
+    #[test_case("usr/bin/tool", "../../bin/../..", false ; "back and forth")]
+    fn check_link_target_tests(entry: &'_ str, target: &'_ str, expected: bool) {
+        assert_eq!(
+            check_link_target("test", Path::new(entry), Path::new(target)).is_ok(),
+            expected
+        )
+    }
+}
@@ -1,7 +1,7 @@
-use super::{Archiver, ArchiverError};
+use super::{safety, ArchiveFormat, Archiver, ArchiverError};
 use async_trait::async_trait;
-use std::{fs::File, path::Path};
-use tar::Archive;
+use std::{fs::File, io::Read, path::Path};
+use tar::{Archive, EntryType};
 use tracing::debug;
 
 pub struct TarArchiver;
@@ -17,63 +17,168 @@ impl Archiver for TarArchiver {
             file_path: file_path.to_path_buf(),
             source: open_err,
         })?;
-        let mut archive = Archive::new(file);
+        unpack("tar", Archive::new(file), file_path, out_dir)
+    }
+
+    async fn extract(&self, file_path: &'_ Path) -> super::Result<()> {
+        self.extract_to(file_path, Path::new(".")).await
+    }
+
+    fn format(&self) -> ArchiveFormat {
+        ArchiveFormat::Tar
+    }
 
-        if let Ok(entries) = archive.entries() {
-            for entry in entries {
-                match entry {
-                    Ok(mut file) => {
-                        let out_file_name = file.path().unwrap();
-                        debug!(
-                            "Extracting '{}' from archive '{}'",
-                            out_file_name.display(),
+    fn name(&self) -> &'static str {
+        "tar"
+    }
+}
+
+/// Unpack every entry of a tar archive, `reader` can be the decompressed stream of a compressed
+/// archive.
+pub(super) fn unpack<R: Read>(
+    extractor: &'_ str,
+    mut archive: Archive<R>,
+    file_path: &'_ Path,
+    out_dir: &'_ Path,
+) -> super::Result<()> {
+    let out_dir = safety::resolve_out_dir(out_dir)?;
+    if let Ok(entries) = archive.entries() {
+        for entry in entries {
+            match entry {
+                Ok(mut file) => {
+                    let to_entry_err = |entry_err: std::io::Error| ArchiverError::ExtractorError {
+                        extractor: extractor.to_string(),
+                        message: format!(
+                            "Unable to read an entry of archive {}. {entry_err}",
                             file_path.display()
-                        );
-                        // Write the file to the specified path
-                        file.unpack_in(out_dir).map_err(|unpack_err| {
-                            ArchiverError::ExtractorError {
-                                extractor: "tar".to_string(),
-                                message: unpack_err.to_string(),
-                            }
-                        })?;
-                    }
-                    Err(entry_err) => {
-                        return Err(ArchiverError::ExtractorError {
-                            extractor: "tar".to_string(),
-                            message: format!(
-                                "Unable to extract file from archive {}. {:?}",
-                                file_path.display(),
-                                entry_err,
-                            ),
-                        })
+                        ),
+                    };
+                    let out_file_name =
+                        safety::enclosed_path(extractor, &file.path().map_err(to_entry_err)?)?;
+                    match (file.header().entry_type(), file.link_name()) {
+                        (EntryType::Symlink, Ok(Some(target))) => {
+                            safety::check_link_target(extractor, &out_file_name, &target)?
+                        }
+                        // hard links name their target from the root of the archive
+                        (EntryType::Link, Ok(Some(target))) => {
+                            let target = safety::enclosed_path(extractor, &target)?;
+                            safety::check_destination(extractor, &out_dir, &out_dir.join(target))?
+                        }
+                        (_, Err(entry_err)) => return Err(to_entry_err(entry_err)),
+                        _ => (),
                     }
+                    safety::check_destination(extractor, &out_dir, &out_dir.join(&out_file_name))?;
+                    debug!(
+                        "Extracting '{}' from archive '{}'",
+                        out_file_name.display(),
+                        file_path.display()
+                    );
+                    // Write the file to the specified path
+                    file.unpack_in(&out_dir).map_err(|unpack_err| {
+                        ArchiverError::ExtractorError {
+                            extractor: extractor.to_string(),
+                            message: unpack_err.to_string(),
+                        }
+                    })?;
+                }
+                Err(entry_err) => {
+                    return Err(ArchiverError::ExtractorError {
+                        extractor: extractor.to_string(),
+                        message: format!(
+                            "Unable to extract file from archive {}. {:?}",
+                            file_path.display(),
+                            entry_err,
+                        ),
+                    })
                 }
             }
-        } else {
-            return Err(ArchiverError::ExtractorError {
-                extractor: "tar".to_string(),
-                message: format!(
-                    "Unable to get archiver entries from {}",
-                    file_path.display()
-                ),
-            });
         }
+    } else {
+        return Err(ArchiverError::ExtractorError {
+            extractor: extractor.to_string(),
+            message: format!(
+                "Unable to get archiver entries from {}",
+                file_path.display()
+            ),
+        });
+    }
+
+    Ok(())
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::test_support::TempDir, tar::Header, test_case::test_case};
+
+    /// Append an entry without the validation of `tar::Builder`, it refuses to write unsafe paths.
+    fn append_raw(archive: &mut Vec<u8>, (name, entry_type, link_name): (&str, EntryType, &str)) {
+        let contents = b"#!/bin/sh\n";
+        let mut header = Header::new_old();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.as_old_mut().linkname[..link_name.len()].copy_from_slice(link_name.as_bytes());
+        header.set_entry_type(entry_type);
+        header.set_size(if entry_type.is_file() {
+            contents.len() as u64
+        } else {
+            0
+        });
+        header.set_mode(0o755);
+        header.set_cksum();
+        archive.extend_from_slice(header.as_bytes());
+        if entry_type.is_file() {
+            archive.extend_from_slice(contents);
+            archive.resize(archive.len() + 512 - contents.len(), 0);
+        }
     }
 
-    async fn extract(&self, file_path: &'_ Path) -> super::Result<()> {
-        self.extract_to(file_path, Path::new(".")).await
+    fn unpack_raw(
+        test_name: &'_ str,
+        entries: &'_ [(&str, EntryType, &str)],
+    ) -> (super::super::Result<()>, bool) {
+        let mut archive = Vec::new();
+        for entry in entries {
+            append_raw(&mut archive, *entry);
+        }
+        archive.resize(archive.len() + 1024, 0);
+        let temp_dir = TempDir::new(&format!("tar-{test_name}"));
+        let base_dir = temp_dir.path();
+        let result = unpack(
+            "tar",
+            Archive::new(&archive[..]),
+            Path::new("tool.tar"),
+            &base_dir.join("out"),
+        );
+        let escaped = base_dir.join("escaped").exists();
+        (result, escaped)
     }
 
-    fn can_handle(&self, file_path: &'_ Path) -> bool {
-        file_path
-            .file_name()
-            .map(|f| f.to_str().unwrap_or_default().ends_with(".tar"))
-            .unwrap_or_default()
+    #[test_case("absolute", &[("/escaped", EntryType::Regular, "")] ; "absolute path")]
+    #[test_case("parent", &[("bin/../../escaped", EntryType::Regular, "")] ; "parent directory")]
+    #[test_case("absolute-link", &[("tool", EntryType::Symlink, "/bin/sh")] ; "absolute symlink")]
+    #[test_case("link", &[("bin/tool", EntryType::Symlink, "../../escaped")] ; "symlink outside")]
+    #[test_case("hardlink", &[("tool", EntryType::Link, "../escaped")] ; "hardlink outside")]
+    #[test_case("through-link", &[
+        ("current", EntryType::Symlink, "."),
+        ("parent", EntryType::Symlink, "current/.."),
+        ("parent/escaped", EntryType::Regular, ""),
+    ] ; "write through symlinks")]
+    fn refuse_unsafe_entries(test_name: &'_ str, entries: &'_ [(&str, EntryType, &str)]) {
+        let (result, escaped) = unpack_raw(test_name, entries);
+        assert!(matches!(result, Err(ArchiverError::UnsafeEntry { .. })));
+        assert!(!escaped);
     }
 
-    fn name(&self) -> &'static str {
-        "tar"
+    #[test]
+    fn links_inside_the_archive() {
+        let (result, _) = unpack_raw(
+            "inside",
+            &[
+                ("./", EntryType::Directory, ""),
+                ("./usr/bin/tool", EntryType::Regular, ""),
+                ("tool", EntryType::Symlink, "usr/bin/tool"),
+                ("usr/bin/tool-link", EntryType::Link, "usr/bin/tool"),
+            ],
+        );
+        result.unwrap();
     }
 }
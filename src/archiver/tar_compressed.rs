@@ -0,0 +1,43 @@
+use super::{ArchiveFormat, Archiver, ArchiverError, Compression, Result};
+use async_trait::async_trait;
+use std::{fs::File, path::Path};
+use tar::Archive;
+
+/// Extracts tar archives compressed as a whole, `.tar.gz`, `.tar.xz`, `.tar.bz2` or `.tar.zst`.
+pub struct TarCompressedArchiver(pub Compression);
+
+#[async_trait()]
+impl Archiver for TarCompressedArchiver {
+    async fn extract_to(&self, file_path: &'_ Path, out_dir: &'_ Path) -> Result<()> {
+        tracing::debug!(
+            "Extracting {} using the '{}' Archiver",
+            file_path.display(),
+            self.name()
+        );
+        let decoder = File::open(file_path)
+            .and_then(|file| self.0.decoder(file))
+            .map_err(|open_err| ArchiverError::Io {
+                file_path: file_path.to_path_buf(),
+                source: open_err,
+            })?;
+        // the tar is read from the decompressed stream, nothing is written next to the archive
+        super::tar::unpack(self.name(), Archive::new(decoder), file_path, out_dir)
+    }
+
+    async fn extract(&self, file_path: &'_ Path) -> Result<()> {
+        self.extract_to(file_path, Path::new(".")).await
+    }
+
+    fn format(&self) -> ArchiveFormat {
+        ArchiveFormat::CompressedTar(self.0)
+    }
+
+    fn name(&self) -> &'static str {
+        match self.0 {
+            Compression::Gzip => "tar-gzip",
+            Compression::Xz => "tar-xz",
+            Compression::Bzip2 => "tar-bzip2",
+            Compression::Zstd => "tar-zstd",
+        }
+    }
+}
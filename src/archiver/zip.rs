@@ -1,6 +1,6 @@
-use super::{Archiver, ArchiverError};
+use super::{safety, ArchiveFormat, Archiver, ArchiverError};
 use async_trait::async_trait;
-use std::path::Path;
+use std::{io::Read, path::Path};
 use tracing::debug;
 use zip::ZipArchive;
 
@@ -26,51 +26,72 @@ impl Archiver for ZipArchiver {
                 message: zip_err.to_string(),
             })?;
 
+        let out_dir = safety::resolve_out_dir(out_dir)?;
         for i in 0..archive.len() {
-            let mut archive_file = archive.by_index(i).unwrap();
-            let out_path = match archive_file.enclosed_name() {
-                Some(path) => path.to_owned(),
-                None => continue,
-            };
-            let out_path = out_dir.join(out_path);
+            let mut archive_file = archive.by_index(i).map_err(|zip_err| {
+                ArchiverError::ExtractorUnableToLoadFile {
+                    extractor: "zip".to_string(),
+                    file_path: file_path.to_path_buf(),
+                    message: zip_err.to_string(),
+                }
+            })?;
+            let relative_path = safety::enclosed_path("zip", Path::new(archive_file.name()))?;
+            let out_path = out_dir.join(&relative_path);
+            safety::check_destination("zip", &out_dir, &out_path)?;
             debug!(
                 "Extracting '{}' from archive '{}'",
                 out_path.display(),
                 file_path.display()
             );
-            if archive_file.is_dir() {
-                // create directory
-                std::fs::create_dir_all(&out_path).map_err(|create_err| {
+            let to_io_err = |out_path: &Path, source| ArchiverError::Io {
+                file_path: out_path.to_path_buf(),
+                source,
+            };
+            let create_dir = |dir: &Path| {
+                std::fs::create_dir_all(dir).map_err(|create_err| {
                     ArchiverError::FailedToCreateDirectory {
-                        file_path: out_path,
+                        file_path: dir.to_path_buf(),
                         source: create_err,
                     }
-                })?;
-            } else {
-                // write file
-                let mut out_file = std::fs::File::create(&out_path).map_err(|file_create_err| {
-                    ArchiverError::FailedToCreateFile {
-                        file_path: out_path.clone(),
-                        source: file_create_err,
-                    }
-                })?;
-                std::io::copy(&mut archive_file, &mut out_file).map_err(|copy_err| {
-                    ArchiverError::Io {
-                        file_path: out_path.clone(),
-                        source: copy_err,
-                    }
-                })?;
-                #[cfg(unix)]
-                {
-                    use std::os::unix::fs::PermissionsExt;
+                })
+            };
+            if archive_file.is_dir() {
+                // create directory
+                create_dir(&out_path)?;
+                continue;
+            }
+            if let Some(parent) = out_path.parent() {
+                create_dir(parent)?;
+            }
+            safety::remove_existing_link(&out_path)?;
+            let mode = archive_file.unix_mode();
+            if mode.map_or(false, |mode| mode & 0o170000 == 0o120000) {
+                // the target of a link is stored as its contents
+                let mut target = String::new();
+                archive_file
+                    .read_to_string(&mut target)
+                    .map_err(|read_err| to_io_err(&out_path, read_err))?;
+                safety::check_link_target("zip", &relative_path, Path::new(&target))?;
+                std::os::unix::fs::symlink(&target, &out_path)
+                    .map_err(|link_err| to_io_err(&out_path, link_err))?;
+                continue;
+            }
+            // write file
+            let mut out_file = std::fs::File::create(&out_path).map_err(|file_create_err| {
+                ArchiverError::FailedToCreateFile {
+                    file_path: out_path.clone(),
+                    source: file_create_err,
+                }
+            })?;
+            std::io::copy(&mut archive_file, &mut out_file)
+                .map_err(|copy_err| to_io_err(&out_path, copy_err))?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
 
-                    if let Some(mode) = archive_file.unix_mode() {
-                        std::fs::set_permissions(&out_path, std::fs::Permissions::from_mode(mode))
-                            .map_err(|permission_err| ArchiverError::Io {
-                                file_path: out_path,
-                                source: permission_err,
-                            })?;
-                    }
+                if let Some(mode) = mode {
+                    std::fs::set_permissions(&out_path, std::fs::Permissions::from_mode(mode))
+                        .map_err(|permission_err| to_io_err(&out_path, permission_err))?;
                 }
             }
         }
@@ -82,18 +103,74 @@ impl Archiver for ZipArchiver {
         self.extract_to(file_path, Path::new(".")).await
     }
 
-    fn can_handle(&self, file_path: &'_ Path) -> bool {
-        file_path
-            .file_name()
-            .map(|f| f.to_str().unwrap_or_default().ends_with(".zip"))
-            .unwrap_or_default()
-        // match std::fs::File::open(file_path) {
-        //     Ok(file) => ZipArchive::new(file).is_ok(),
-        //     Err(_) => false,
-        // }
+    fn format(&self) -> ArchiveFormat {
+        ArchiveFormat::Zip
     }
 
     fn name(&self) -> &'static str {
         "zip"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::TempDir,
+        std::io::Write,
+        test_case::test_case,
+        zip::{write::FileOptions, ZipWriter},
+    };
+
+    /// Extract a zip archive of files, and of symbolic links when a target is given.
+    fn extract_entries(
+        test_name: &'_ str,
+        entries: &'_ [(&str, Option<&str>)],
+    ) -> (super::super::Result<()>, bool) {
+        let temp_dir = TempDir::new(&format!("zip-{test_name}"));
+        let base_dir = temp_dir.path();
+        let file_path = base_dir.join("tool.zip");
+        let mut writer = ZipWriter::new(std::fs::File::create(&file_path).unwrap());
+        for (name, target) in entries {
+            match target {
+                Some(target) => writer
+                    .add_symlink(*name, *target, FileOptions::default())
+                    .unwrap(),
+                None => {
+                    writer.start_file(*name, FileOptions::default()).unwrap();
+                    writer.write_all(b"#!/bin/sh\n").unwrap();
+                }
+            }
+        }
+        writer.finish().unwrap();
+
+        let result =
+            async_std::task::block_on(ZipArchiver.extract_to(&file_path, &base_dir.join("out")));
+        let escaped = base_dir.join("escaped").exists();
+        (result, escaped)
+    }
+
+    #[test_case("absolute", &[("/escaped", None)] ; "absolute path")]
+    #[test_case("parent", &[("bin/../../escaped", None)] ; "parent directory")]
+    #[test_case("absolute-link", &[("tool", Some("/bin/sh"))] ; "absolute symlink")]
+    #[test_case("link", &[("bin/tool", Some("../../escaped"))] ; "symlink outside")]
+    #[test_case("through-link", &[
+        ("current", Some(".")),
+        ("parent", Some("current/..")),
+        ("parent/escaped", None),
+    ] ; "write through symlinks")]
+    fn refuse_unsafe_entries(test_name: &'_ str, entries: &'_ [(&str, Option<&str>)]) {
+        let (result, escaped) = extract_entries(test_name, entries);
+        assert!(matches!(result, Err(ArchiverError::UnsafeEntry { .. })));
+        assert!(!escaped);
+    }
+
+    #[test]
+    fn links_inside_the_archive() {
+        let (result, _) = extract_entries(
+            "inside",
+            &[("usr/bin/tool", None), ("tool", Some("usr/bin/tool"))],
+        );
+        result.unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use sha2::{Digest, Sha256, Sha512};
+use std::{fs::File, path::Path};
+
+/// Names of files that list the checksums for every asset in a release, `checksums.txt`,
+/// `SHA256SUMS`, `tool_1.0.0_checksums.txt` and the like.
+static CHECKSUMS_FILE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)(^|[._-])(checksums?|sha(256|512)sums?)(\.txt)?$"#)
+        .expect("unable to create regex for checksum files")
+});
+
+/// Calculate the hex encoded SHA-256 digest of a file.
+pub fn sha256_file(file_path: &'_ Path) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut File::open(file_path)?, &mut hasher)?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// Calculate the hex encoded SHA-512 digest of a file.
+pub fn sha512_file(file_path: &'_ Path) -> std::io::Result<String> {
+    let mut hasher = Sha512::new();
+    std::io::copy(&mut File::open(file_path)?, &mut hasher)?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// Calculate the digest of a file using the same algorithm as the expected digest, which is
+/// determined by its length.
+pub fn digest_file_like(file_path: &'_ Path, expected: &'_ str) -> std::io::Result<String> {
+    match expected.len() {
+        128 => sha512_file(file_path),
+        _ => sha256_file(file_path),
+    }
+}
+
+fn to_hex(bytes: &'_ [u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Find the files in a release that could contain a checksum for `asset_name`, ordered from the
+/// most to the least specific.
+pub fn find_checksum_asset_names<'a>(
+    asset_name: &'_ str,
+    release_asset_names: &'_ [&'a str],
+) -> Vec<&'a str> {
+    let dedicated: Vec<&str> = ["sha256", "sha256sum", "sha512", "sha512sum"]
+        .iter()
+        .filter_map(|ext| {
+            let checksum_name = format!("{asset_name}.{ext}");
+            release_asset_names
+                .iter()
+                .find(|name| name.eq_ignore_ascii_case(&checksum_name))
+                .copied()
+        })
+        .collect();
+
+    dedicated
+        .into_iter()
+        .chain(
+            release_asset_names
+                .iter()
+                .filter(|name| CHECKSUMS_FILE_REGEX.is_match(name))
+                .copied(),
+        )
+        .collect()
+}
+
+/// Find the digest for `asset_name` in the contents of a checksum file.
+///
+/// Supports the GNU coreutils format (`<digest>  <file>`, with an optional `*` for binary mode),
+/// the BSD format (`SHA256 (<file>) = <digest>`) and files that only contain a digest.
+pub fn parse_checksum(contents: &'_ str, asset_name: &'_ str) -> Option<String> {
+    let is_digest =
+        |s: &str| (s.len() == 64 || s.len() == 128) && s.chars().all(|c| c.is_ascii_hexdigit());
+    let lines: Vec<&str> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    for line in lines.iter() {
+        if let Some((algo_file, digest)) = line.split_once(" = ") {
+            let file_name = algo_file
+                .split_once('(')
+                .map(|(_, f)| f.trim_end_matches(')'));
+            if file_name == Some(asset_name) && is_digest(digest.trim()) {
+                return Some(digest.trim().to_lowercase());
+            }
+        } else if let Some((digest, file_name)) = line.split_once(char::is_whitespace) {
+            let file_name = file_name.trim().trim_start_matches('*');
+            let file_name = file_name.rsplit('/').next().unwrap_or(file_name);
+            if file_name == asset_name && is_digest(digest) {
+                return Some(digest.to_lowercase());
+            }
+        }
+    }
+
+    // files dedicated to a single asset often only contain the digest
+    match lines.as_slice() {
+        [only_line] if is_digest(only_line) => Some(only_line.to_lowercase()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    const DIGEST: &str = "6b2bd4d4c9a0d4c1a3b0f0b8d2e1a0f9c8b7a6d5e4f3a2b1c0d9e8f7a6b5c4d3";
+
+    #[test_case(&format!("{DIGEST}  gh_2.10.1_linux_amd64.tar.gz\n{}  gh_2.10.1_macOS_amd64.tar.gz", "0".repeat(64)), Some(DIGEST) ; "coreutils format")]
+    #[test_case(&format!("{DIGEST} *gh_2.10.1_linux_amd64.tar.gz"), Some(DIGEST) ; "coreutils binary mode")]
+    #[test_case(&format!("{DIGEST}  ./dist/gh_2.10.1_linux_amd64.tar.gz"), Some(DIGEST) ; "coreutils with a path")]
+    #[test_case(&format!("SHA256 (gh_2.10.1_linux_amd64.tar.gz) = {DIGEST}"), Some(DIGEST) ; "bsd format")]
+    #[test_case(&format!("{}\n", DIGEST.to_uppercase()), Some(DIGEST) ; "digest only")]
+    #[test_case(&format!("{DIGEST}  gh_2.10.1_linux_arm64.tar.gz"), None ; "different asset")]
+    #[test_case("not a checksum file", None ; "no digest")]
+    fn parse_checksum_tests(contents: &'_ str, expected: Option<&'_ str>) {
+        assert_eq!(
+            parse_checksum(contents, "gh_2.10.1_linux_amd64.tar.gz"),
+            expected.map(String::from)
+        )
+    }
+
+    #[test_case(&["gh_2.10.1_checksums.txt", "gh_2.10.1_linux_amd64.tar.gz"], vec!["gh_2.10.1_checksums.txt"] ; "release checksums file")]
+    #[test_case(&["SHA256SUMS", "SHA256SUMS.sig", "gh_2.10.1_linux_amd64.tar.gz"], vec!["SHA256SUMS"] ; "sha256sums")]
+    #[test_case(&["checksums.txt", "gh_2.10.1_linux_amd64.tar.gz.sha256"], vec!["gh_2.10.1_linux_amd64.tar.gz.sha256", "checksums.txt"] ; "dedicated file first")]
+    #[test_case(&["gh_2.10.1_linux_amd64.tar.gz", "gh_2.10.1_linux_arm64.tar.gz.sha256"], vec![] ; "none")]
+    fn find_checksum_asset_names_tests(names: &'_ [&'_ str], expected: Vec<&'_ str>) {
+        assert_eq!(
+            find_checksum_asset_names("gh_2.10.1_linux_amd64.tar.gz", names),
+            expected
+        )
+    }
+}
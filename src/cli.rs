@@ -1,9 +1,12 @@
 use crate::{
     actions::{self, ActionsError},
+    download::RetryPolicy,
     environment::{Environment, EnvironmentError, EnvironmentLoadError},
-    system::System,
+    system::{Libc, System, SystemError},
+    template::{UrlTemplate, VersionList},
+    tool::Binary,
 };
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 use clap_verbosity_flag::Verbosity;
 use std::{fmt::Display, path::PathBuf};
 use thiserror::Error;
@@ -15,7 +18,7 @@ pub struct Cli {
     #[command(flatten)]
     pub verbose: Verbosity,
     /// Where to store the data application data
-    #[arg(short, long, value_hint = clap::ValueHint::DirPath)]
+    #[arg(short, long, value_hint = clap::ValueHint::DirPath, conflicts_with = "local")]
     pub data_dir: Option<PathBuf>,
     /// Environment where the tool will be installed to
     #[arg(short, long, default_value = "global")]
@@ -23,15 +26,122 @@ pub struct Cli {
     /// A GitHub API token to use authenticated requests to the API
     #[arg(long)]
     pub github_token: Option<String>,
+    /// URL of a GitHub Enterprise Server used instead of github.com
+    #[arg(long)]
+    pub github_url: Option<String>,
+    /// URL of the GitLab instance hosting `gitlab:` tools, defaults to https://gitlab.com
+    #[arg(long)]
+    pub gitlab_url: Option<String>,
+    /// A GitLab API token used to access private projects
+    #[arg(long)]
+    pub gitlab_token: Option<String>,
+    /// URL of the Gitea or Forgejo instance hosting `gitea:` tools, defaults to https://gitea.com
+    #[arg(long)]
+    pub gitea_url: Option<String>,
+    /// A Gitea or Forgejo API token used to access private repositories
+    #[arg(long)]
+    pub gitea_token: Option<String>,
     /// Use a local environment
     ///
-    /// Files will be stored in the current directory under a "hidden" folder
+    /// Files will be stored in the current directory under a "hidden" folder. Once created the
+    /// local environment is discovered automatically from any sub directory of the project, as is
+    /// a project containing a `vers.toml` file.
     #[arg(short, long)]
     pub local: bool,
+    #[command(flatten)]
+    pub install: InstallOptions,
     #[command(subcommand)]
     pub action: Actions,
 }
 
+/// Options that apply to every tool installed by a command.
+#[derive(Debug, Clone, Default, Args)]
+pub struct InstallOptions {
+    /// Refuse to install a tool unless its release provides a checksum for the downloaded asset
+    #[arg(long, global = true)]
+    pub require_checksums: bool,
+    /// Number of times a failed download is retried
+    #[arg(long, global = true, default_value_t = 3)]
+    pub retries: u32,
+    /// Seconds to wait before the first retry, doubled after every attempt
+    #[arg(long, global = true, default_value_t = 1)]
+    pub retry_backoff: u64,
+    /// C library assets are picked for on Linux, `gnu` or `musl`, detected when not set.
+    ///
+    /// The libc is remembered by the environment, `VERS_LIBC` overrides it for a single command.
+    #[arg(long, global = true)]
+    pub libc: Option<Libc>,
+    /// Install tools for another platform, as `os/arch` like `linux/arm64`.
+    ///
+    /// The platform is remembered by the environment, tools are selected, locked and synced for it
+    /// and never run while they are installed.
+    #[arg(long, global = true, value_name = "OS/ARCH")]
+    pub platform: Option<System>,
+}
+
+impl InstallOptions {
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            retries: self.retries,
+            backoff: std::time::Duration::from_secs(self.retry_backoff),
+        }
+    }
+}
+
+/// Options for tools downloaded from a URL template, named `url:<name>`.
+#[derive(Debug, Clone, Default, Args)]
+pub struct TemplateArgs {
+    /// URL template used to download the tool.
+    ///
+    /// `{version}`, `{os}` and `{arch}` are replaced for the platform, for example
+    /// `https://dl.k8s.io/release/v{version}/bin/{os}/{arch}/kubectl`.
+    #[arg(long)]
+    pub url_template: Option<String>,
+    /// Spelling of an operating system in the URL template, for example `macos=Darwin`.
+    #[arg(long, value_parser = parse_key_value)]
+    pub os_map: Vec<(String, String)>,
+    /// Spelling of an architecture in the URL template, for example `x86_64=x64`.
+    #[arg(long, value_parser = parse_key_value)]
+    pub arch_map: Vec<(String, String)>,
+    /// GitHub repository whose release tags are the versions of the tool.
+    #[arg(long, conflicts_with = "versions_url")]
+    pub versions_github: Option<String>,
+    /// Document listing the versions of the tool, one version per line.
+    #[arg(long)]
+    pub versions_url: Option<String>,
+    /// Regular expression finding the versions in `--versions-url`, the first capture group is
+    /// used when the pattern has one.
+    #[arg(long, requires = "versions_url")]
+    pub versions_pattern: Option<String>,
+}
+
+impl TemplateArgs {
+    pub fn template(&self) -> Option<UrlTemplate> {
+        let url = self.url_template.as_ref()?;
+        let versions = match (&self.versions_github, &self.versions_url) {
+            (Some(repo), _) => VersionList::GitHub(repo.to_string()),
+            (None, Some(url)) => VersionList::Url {
+                url: url.to_string(),
+                pattern: self.versions_pattern.clone(),
+            },
+            (None, None) => VersionList::None,
+        };
+        Some(UrlTemplate {
+            url: url.to_string(),
+            os: self.os_map.iter().cloned().collect(),
+            arch: self.arch_map.iter().cloned().collect(),
+            versions,
+        })
+    }
+}
+
+fn parse_key_value(s: &'_ str) -> std::result::Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected `key=value`, got '{s}'")),
+    }
+}
+
 impl Cli {
     fn new() -> Self {
         Self::parse()
@@ -44,15 +154,29 @@ impl Default for Cli {
     }
 }
 
+// parsed once per run, boxing the arguments of `add` wouldn't gain anything
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Subcommand)]
 pub enum Actions {
     /// Add a tool to the designated environment
+    #[command(group(ArgGroup::new("tool_name").args(["name", "local_name"]).required(true)))]
     Add {
         /// name of the tool to install to the environment.
         ///
         /// To install a specific version use name@version, for example:
         /// `cli/cli@v2.4.0` version should be a release tag.
-        name: String,
+        name: Option<String>,
+        /// Install the tool from a local archive or directory instead of downloading it.
+        #[arg(long, value_hint = clap::ValueHint::AnyPath)]
+        from_file: Option<PathBuf>,
+        /// name of a tool installed using `--from-file`, for example `acme/mytool@1.2.3`.
+        #[arg(
+            long = "name",
+            id = "local_name",
+            value_name = "NAME",
+            requires = "from_file"
+        )]
+        local_name: Option<String>,
         /// Alias to use instead of the repository name.
         ///
         /// This is how you will call the tool on the command line.
@@ -67,6 +191,11 @@ pub enum Actions {
         /// Filter used to find the executable to link into the environment.
         #[arg(short, long)]
         file_filter: Option<String>,
+        /// Another executable of the tool to link into the environment, as `PATTERN[=LINK]`.
+        ///
+        /// Can be repeated, `--bin gofmt` links the `gofmt` shipped with `go` next to it.
+        #[arg(long = "bin", value_name = "PATTERN[=LINK]")]
+        binaries: Vec<Binary>,
         /// Allow install of pre-release versions of the tool.
         ///
         /// When `show` is provided this includes pre-release versions in the list,
@@ -80,6 +209,34 @@ pub enum Actions {
         /// first selected will be set up to use in the environment.
         #[arg(short = 'S', long)]
         show: bool,
+        /// Show how the assets of the release score for this platform instead of installing one.
+        ///
+        /// Lists every asset with its score and the reasons for it, the eligible asset with the
+        /// highest score is the one that gets installed.
+        #[arg(long)]
+        explain: bool,
+        /// URL of the self-hosted forge the tool is released on.
+        ///
+        /// Stored with the tool in the environment, use it for tools on a GitHub Enterprise
+        /// Server or another GitLab, Gitea or Forgejo instance than the configured one.
+        #[arg(long)]
+        base_url: Option<String>,
+        /// Source that builds the tool when a release has no asset for this platform.
+        ///
+        /// For example `cargo:ripgrep` runs `cargo install ripgrep` for the same version.
+        #[arg(long, value_name = "NAME")]
+        fallback: Option<String>,
+        #[command(flatten)]
+        template: TemplateArgs,
+        /// minisign public key file used to verify the release signatures.
+        #[arg(long, value_hint = clap::ValueHint::FilePath)]
+        minisign_key: Option<PathBuf>,
+        /// cosign public key file used to verify the release signatures.
+        #[arg(long, value_hint = clap::ValueHint::FilePath)]
+        cosign_key: Option<PathBuf>,
+        /// ASCII armored GPG public key file used to verify the release signatures.
+        #[arg(long, value_hint = clap::ValueHint::FilePath)]
+        gpg_key: Option<PathBuf>,
     },
     /// Remove a tool from the designated environment
     Remove {
@@ -104,7 +261,15 @@ pub enum Actions {
         output: ListOutputType,
     },
     /// sync all version information with listed in the env config file.
-    Sync,
+    ///
+    /// Tools are installed strictly from `envs/vers.lock`, the lock file next to `envs/<env>.json`
+    /// holding the locks of every environment, commit both to share the exact assets.
+    Sync {
+        /// Resolve tools missing from the lock or locked at another version again instead of
+        /// failing, they are locked afterwards
+        #[arg(long)]
+        allow_unlocked: bool,
+    },
     /// Update tools to the latest version available from GitHub.
     Update {
         /// Which tool to upgrade, when omitted all tools in the environment will be upgraded.
@@ -138,42 +303,129 @@ pub enum ActionErrors {
     EnvironmentLoadError(#[from] EnvironmentLoadError),
     #[error(transparent)]
     ActionError(#[from] ActionsError),
+    #[error(transparent)]
+    SystemError(#[from] SystemError),
 }
 
 type Result<T, E = ActionErrors> = std::result::Result<T, E>;
 
 impl Actions {
-    pub async fn execute(&self, config_dir: PathBuf, env_name: &'_ str) -> Result<()> {
-        let system = System::default();
+    pub async fn execute(
+        &self,
+        config_dir: PathBuf,
+        env_name: &'_ str,
+        options: &'_ InstallOptions,
+    ) -> Result<()> {
         let mut loaded_env = Environment::load(&config_dir, env_name).await?;
+        if let Some(platform) = &options.platform {
+            // naming the machine vers runs on goes back to installing tools for it
+            loaded_env.platform = (!platform.is_host()).then(|| platform.platform());
+        }
+        let platform = match &options.platform {
+            Some(platform) => Some(platform.clone()),
+            None => loaded_env
+                .platform
+                .as_deref()
+                .map(str::parse::<System>)
+                .transpose()?,
+        };
+        let mut system = match platform {
+            Some(platform) if !platform.is_host() => platform,
+            _ => System::host()?,
+        };
+        if let Some(libc) = options.libc {
+            loaded_env.libc = Some(libc.to_string());
+        }
+        // `--libc` wins over `VERS_LIBC`, which wins over the libc stored in the environment
+        let parse_libc = |origin: &str, libc: &str| {
+            libc.parse::<Libc>()
+                .map_err(|message| SystemError::InvalidLibc {
+                    origin: origin.to_string(),
+                    message,
+                })
+        };
+        let libc = match options.libc {
+            Some(libc) => Some(libc),
+            None => match std::env::var("VERS_LIBC") {
+                Ok(env_libc) => Some(parse_libc("VERS_LIBC variable", &env_libc)?),
+                Err(_) => loaded_env
+                    .libc
+                    .as_deref()
+                    .map(|env_libc| parse_libc("environment file", env_libc))
+                    .transpose()?,
+            },
+        };
+        if let (Some(libc), Some(_)) = (libc, system.libc) {
+            system.libc = Some(libc);
+        }
         match self {
             Actions::Add {
                 name,
+                from_file,
+                local_name,
                 alias,
                 asset_pattern,
                 file_filter,
+                binaries,
                 pre_release,
                 show,
-            } => Ok(actions::add_new_tool(
-                &mut loaded_env,
-                name,
-                &system,
-                actions::Patterns {
+                explain,
+                base_url,
+                fallback,
+                template,
+                minisign_key,
+                cosign_key,
+                gpg_key,
+            } => {
+                let name = name
+                    .as_ref()
+                    .or(local_name.as_ref())
+                    .expect("clap requires a tool name");
+                let patterns = actions::Patterns {
                     asset: asset_pattern.to_owned(),
                     file: file_filter.to_owned(),
-                },
-                alias.to_owned(),
-                *show,
-                *pre_release,
-            )
-            .await?),
+                    binaries: binaries.to_owned(),
+                };
+                match from_file {
+                    Some(file_path) => Ok(actions::add_local_tool(
+                        &mut loaded_env,
+                        file_path,
+                        name,
+                        patterns,
+                        alias.to_owned(),
+                    )
+                    .await?),
+                    None => Ok(actions::add_new_tool(
+                        &mut loaded_env,
+                        name,
+                        &system,
+                        patterns,
+                        alias.to_owned(),
+                        *show,
+                        *pre_release,
+                        *explain,
+                        actions::SourceOptions {
+                            base_url: base_url.to_owned(),
+                            template: template.template(),
+                            fallback: fallback.to_owned(),
+                        },
+                        actions::SignatureKeys {
+                            minisign: minisign_key.to_owned(),
+                            cosign: cosign_key.to_owned(),
+                            gpg: gpg_key.to_owned(),
+                        },
+                        options,
+                    )
+                    .await?),
+                }
+            }
             Actions::Remove {
                 name,
                 all,
                 link_only: _link_only,
             } => Ok(actions::remove_tool(&mut loaded_env, name, *all).await?),
             Actions::List { installed, output } => {
-                Ok(actions::list_tools(&mut loaded_env, *installed, output.to_owned()).await?)
+                Ok(actions::list_tools(&loaded_env, *installed, output.to_owned()).await?)
             }
             Actions::Update { name } => Ok(actions::update_tools(
                 &mut loaded_env,
@@ -183,9 +435,13 @@ impl Actions {
                 } else {
                     actions::UpdateType::All
                 },
+                options,
             )
             .await?),
-            Actions::Completions { shell } => Ok(actions::generate_completions(shell)),
+            Actions::Completions { shell } => {
+                actions::generate_completions(shell);
+                Ok(())
+            }
             Actions::Env {
                 name,
                 shell,
@@ -203,9 +459,12 @@ impl Actions {
                     loaded_env
                 };
 
-                Ok(actions::show_env_config(&env, *bare_path, *shell))
+                actions::show_env_config(&env, *bare_path, *shell);
+                Ok(())
+            }
+            Actions::Sync { allow_unlocked } => {
+                Ok(actions::sync_tools(&mut loaded_env, &system, options, *allow_unlocked).await?)
             }
-            Actions::Sync => Ok(actions::sync_tools(&mut loaded_env, &system).await?),
         }
     }
 }
@@ -1,6 +1,11 @@
 use directories_next::{BaseDirs, ProjectDirs, UserDirs};
 use std::path::{Path, PathBuf};
 
+/// Folder used to store a project local environment.
+pub const LOCAL_CONFIG_DIR_NAME: &str = ".vers";
+/// File that marks the root of a project using a local environment.
+pub const LOCAL_MARKER_FILE_NAME: &str = "vers.toml";
+
 pub fn get_default_config_path() -> PathBuf {
     if let Some(project_dirs) = ProjectDirs::from("dev", "reynn", "vers") {
         project_dirs.config_dir().to_path_buf()
@@ -13,6 +18,48 @@ pub fn get_default_config_path() -> PathBuf {
     }
 }
 
+pub fn get_local_config_path(project_dir: &'_ Path) -> PathBuf {
+    project_dir.join(LOCAL_CONFIG_DIR_NAME)
+}
+
+/// Walk up from `start_dir` looking for a project that has a local environment, either a `.vers`
+/// folder or a `vers.toml` file marks the root of the project.
+pub fn find_local_config_path(start_dir: &'_ Path) -> Option<PathBuf> {
+    start_dir
+        .ancestors()
+        .find(|dir| {
+            dir.join(LOCAL_CONFIG_DIR_NAME).is_dir() || dir.join(LOCAL_MARKER_FILE_NAME).is_file()
+        })
+        .map(get_local_config_path)
+}
+
+/// Create the local config folder for a project, the downloaded tools and links are ignored so
+/// only the environment files end up being committed.
+pub fn create_local_config_path(local_dir: &'_ Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(local_dir)?;
+    let ignore_file = local_dir.join(".gitignore");
+    if !ignore_file.exists() {
+        std::fs::write(ignore_file, "/tools/\n/envs/*/\n")?;
+    }
+    Ok(())
+}
+
+/// The local environment used from `current_dir`, the one of the enclosing project or a new one
+/// in `current_dir` when `create` is set. The folder is set up every time it is used, projects
+/// marked by a `vers.toml` only get their `.vers` folder on first use.
+pub fn use_local_config_path(
+    current_dir: &'_ Path,
+    create: bool,
+) -> std::io::Result<Option<PathBuf>> {
+    let local_dir = match find_local_config_path(current_dir) {
+        Some(local_dir) => local_dir,
+        None if create => get_local_config_path(current_dir),
+        None => return Ok(None),
+    };
+    create_local_config_path(&local_dir)?;
+    Ok(Some(local_dir))
+}
+
 pub fn get_environment_link_path(base_path: &'_ Path, env_name: &'_ str) -> PathBuf {
     base_path.join("envs").join(env_name)
 }
@@ -21,6 +68,11 @@ pub fn get_environment_config_file_path(base_path: &'_ Path, env_name: &'_ str)
     base_path.join("envs").join(format!("{}.json", env_name))
 }
 
+/// The `vers.lock` next to the environment files, shared by every environment of the folder.
+pub fn get_lock_file_path(base_path: &'_ Path) -> PathBuf {
+    base_path.join("envs").join("vers.lock")
+}
+
 pub fn get_tool_link_path(base_path: &'_ Path, tool_alias: &'_ str) -> PathBuf {
     base_path.join(tool_alias)
 }
@@ -40,13 +92,15 @@ pub fn get_tool_download_dir(base_path: &'_ Path, name: &'_ str) -> PathBuf {
         .parent()
         .unwrap()
         .join("tools")
-        .join(name)
+        // `gitlab:group/project` is stored as `gitlab/group/project`
+        .join(name.replace(':', "/"))
 }
 
 #[cfg(test)]
 mod test {
     use {
         super::*,
+        crate::test_support::TempDir,
         std::path::{Path, PathBuf},
         test_case::test_case,
     };
@@ -104,4 +158,36 @@ mod test {
     pub fn test_get_tool_link_path(input: &'_ Path, tool_alias: &'_ str, expected: PathBuf) {
         assert_eq!(get_tool_link_path(input, tool_alias), expected)
     }
+
+    #[test_case(LOCAL_CONFIG_DIR_NAME, true ; "local config folder")]
+    #[test_case(LOCAL_MARKER_FILE_NAME, false ; "marker file")]
+    pub fn test_find_local_config_path(marker: &'_ str, is_dir: bool) {
+        let temp_dir = TempDir::new(&format!("find-local-{marker}"));
+        let project_dir = temp_dir.path();
+        let nested_dir = project_dir.join("src").join("nested");
+        std::fs::create_dir_all(&nested_dir).unwrap();
+        if is_dir {
+            std::fs::create_dir_all(project_dir.join(marker)).unwrap();
+        } else {
+            std::fs::write(project_dir.join(marker), "").unwrap();
+        }
+
+        let found = find_local_config_path(&nested_dir);
+        assert_eq!(found, Some(project_dir.join(LOCAL_CONFIG_DIR_NAME)))
+    }
+
+    #[test]
+    pub fn test_use_local_config_path_from_marker() {
+        let temp_dir = TempDir::new("use-local");
+        let project_dir = temp_dir.path();
+        let nested_dir = project_dir.join("src");
+        std::fs::create_dir_all(&nested_dir).unwrap();
+        std::fs::write(project_dir.join(LOCAL_MARKER_FILE_NAME), "").unwrap();
+
+        let used = use_local_config_path(&nested_dir, false);
+        let ignore_file = project_dir.join(LOCAL_CONFIG_DIR_NAME).join(".gitignore");
+        let ignored = std::fs::read_to_string(ignore_file);
+        assert_eq!(used.unwrap(), Some(project_dir.join(LOCAL_CONFIG_DIR_NAME)));
+        assert_eq!(ignored.unwrap(), "/tools/\n/envs/*/\n");
+    }
 }
@@ -1,8 +1,12 @@
-// use async_std::fs::{create_dir_all, write};
-use octocrab::models::repos::Asset;
-use std::path::PathBuf;
+use async_std::io::WriteExt;
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::{header, StatusCode};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use thiserror::Error;
-use tracing::info;
+use tracing::{debug, info, warn};
 
 #[derive(Debug, Error)]
 pub enum DownloadError {
@@ -23,15 +27,89 @@ pub enum DownloadError {
         url: reqwest::Url,
         source: reqwest::Error,
     },
+    #[error("Failed to get '{url}': server responded with {status}")]
+    HttpStatus {
+        url: reqwest::Url,
+        status: reqwest::StatusCode,
+    },
+    #[error("Failed to get '{0}': too many redirects")]
+    TooManyRedirects(reqwest::Url),
+}
+
+impl DownloadError {
+    /// Whether trying the download again could succeed.
+    fn is_transient(&self) -> bool {
+        match self {
+            DownloadError::ResponseBytes(_) | DownloadError::HttpGet { .. } => true,
+            DownloadError::HttpStatus { status, .. } => {
+                status.is_server_error()
+                    || *status == StatusCode::TOO_MANY_REQUESTS
+                    || *status == StatusCode::REQUEST_TIMEOUT
+            }
+            DownloadError::CreateDirectoryFailed { .. }
+            | DownloadError::FileWrite { .. }
+            | DownloadError::TooManyRedirects(_) => false,
+        }
+    }
+}
+
+/// How often a failed download is attempted again, the delay doubles after every attempt.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Redirects followed before a download is given up on.
+const MAX_REDIRECTS: usize = 10;
+
+/// A header authenticating downloads from the forge a tool is released on, like the
+/// `PRIVATE-TOKEN` of a GitLab instance. Release assets can link to any host, the header is only
+/// sent to the host of the forge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadAuth {
+    pub base_url: reqwest::Url,
+    pub header: &'static str,
+    pub value: String,
+}
+
+impl DownloadAuth {
+    /// Whether the header may be sent with a request to `url`, it has to be on the same host and
+    /// port as the forge.
+    pub fn applies_to(&self, url: &'_ reqwest::Url) -> bool {
+        host_of(url).map_or(false, |host| Some(host) == host_of(&self.base_url))
+    }
+}
+
+/// The host and port of a URL, hosts are compared case insensitive.
+pub fn host_of(url: &'_ reqwest::Url) -> Option<(String, Option<u16>)> {
+    Some((url.host_str()?.to_lowercase(), url.port_or_known_default()))
 }
 
 /// Download a file from a provided URL
+///
+/// The response is streamed to a `.part` file next to the destination that is renamed into place
+/// once it is complete. A partial file left over by an earlier attempt is resumed using an HTTP
+/// Range request when the server supports it. The `auth` header is only sent to its own host,
+/// redirects to other hosts are followed without it.
 pub async fn download_asset<P: Into<PathBuf>>(
-    asset: &'_ Asset,
+    url: &'_ reqwest::Url,
+    asset_name: &'_ str,
     out_dir: P,
+    retry_policy: &'_ RetryPolicy,
+    auth: Option<&'_ DownloadAuth>,
 ) -> Result<PathBuf, DownloadError> {
     let out_file_name: PathBuf = out_dir.into();
-    let out_file_name = out_file_name.join(&asset.name);
+    let out_file_name = out_file_name.join(asset_name);
     info!("Downloading file to {:?}", &out_file_name);
     if let Some(out_parent) = &out_file_name.parent() {
         match async_std::fs::create_dir_all(out_parent).await {
@@ -44,22 +122,179 @@ pub async fn download_asset<P: Into<PathBuf>>(
             }
         };
     };
-    match reqwest::get(asset.browser_download_url.as_str()).await {
-        Ok(download_result) => match download_result.bytes().await {
-            Ok(download_bytes) => {
-                match async_std::fs::write(&out_file_name, download_bytes).await {
-                    Ok(_) => Ok(out_file_name),
-                    Err(write_err) => Err(DownloadError::FileWrite {
-                        file_path: out_file_name,
-                        source: write_err,
-                    }),
-                }
+
+    let partial_file_name = out_file_name.with_file_name(format!("{asset_name}.part"));
+    let mut attempt = 0;
+    loop {
+        match download_to_file(url, asset_name, &partial_file_name, auth).await {
+            Ok(_) => break,
+            Err(download_err) if download_err.is_transient() && attempt < retry_policy.retries => {
+                let delay = retry_policy.backoff * 2u32.pow(attempt);
+                attempt += 1;
+                warn!(
+                    "Download of {} failed, retrying in {:?} ({}/{}). {}",
+                    url, delay, attempt, retry_policy.retries, download_err
+                );
+                async_std::task::sleep(delay).await;
             }
-            Err(bytes_err) => Err(DownloadError::ResponseBytes(bytes_err)),
-        },
-        Err(down_err) => Err(DownloadError::HttpGet {
-            url: asset.browser_download_url.to_owned(),
-            source: down_err,
+            Err(download_err) => return Err(download_err),
+        }
+    }
+
+    match async_std::fs::rename(&partial_file_name, &out_file_name).await {
+        Ok(_) => Ok(out_file_name),
+        Err(rename_err) => Err(DownloadError::FileWrite {
+            file_path: out_file_name,
+            source: rename_err,
         }),
     }
 }
+
+/// Get a URL following redirects by hand, so the auth header never reaches another host.
+async fn get(
+    url: &'_ reqwest::Url,
+    resume_from: u64,
+    auth: Option<&'_ DownloadAuth>,
+) -> Result<reqwest::Response, DownloadError> {
+    let to_get_err = |url: &reqwest::Url, source| DownloadError::HttpGet {
+        url: url.to_owned(),
+        source,
+    };
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|build_err| to_get_err(url, build_err))?;
+    let mut url = url.to_owned();
+    for _ in 0..=MAX_REDIRECTS {
+        let mut request = client.get(url.as_str());
+        if resume_from > 0 {
+            request = request.header(header::RANGE, format!("bytes={resume_from}-"));
+        }
+        if let Some(auth) = auth.filter(|auth| auth.applies_to(&url)) {
+            request = request.header(auth.header, &auth.value);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|get_err| to_get_err(&url, get_err))?;
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| url.join(location).ok());
+        match location {
+            Some(location) if response.status().is_redirection() => {
+                debug!("Following redirect of {} to {}", url, location);
+                url = location;
+            }
+            _ => return Ok(response),
+        }
+    }
+    Err(DownloadError::TooManyRedirects(url))
+}
+
+async fn download_to_file(
+    url: &'_ reqwest::Url,
+    asset_name: &'_ str,
+    partial_file_name: &'_ Path,
+    auth: Option<&'_ DownloadAuth>,
+) -> Result<(), DownloadError> {
+    let to_write_err = |source| DownloadError::FileWrite {
+        file_path: partial_file_name.to_path_buf(),
+        source,
+    };
+    let mut resume_from = async_std::fs::metadata(partial_file_name)
+        .await
+        .map(|m| m.len())
+        .unwrap_or_default();
+
+    let mut response = loop {
+        if resume_from > 0 {
+            debug!("Resuming download of {} from byte {}", url, resume_from);
+        }
+        let response = get(url, resume_from, auth).await?;
+        if response.status() != StatusCode::RANGE_NOT_SATISFIABLE || resume_from == 0 {
+            break response;
+        }
+
+        // the partial file is either already complete or no longer matches the remote file
+        let complete_size = response
+            .headers()
+            .get(header::CONTENT_RANGE)
+            .and_then(|range| range.to_str().ok())
+            .and_then(|range| range.rsplit('/').next())
+            .and_then(|size| size.parse::<u64>().ok());
+        if complete_size == Some(resume_from) {
+            return Ok(());
+        }
+        async_std::fs::remove_file(partial_file_name)
+            .await
+            .map_err(to_write_err)?;
+        resume_from = 0;
+    };
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(DownloadError::HttpStatus {
+            url: url.to_owned(),
+            status,
+        });
+    }
+
+    let append = resume_from > 0 && status == StatusCode::PARTIAL_CONTENT;
+    let mut out_file = async_std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(partial_file_name)
+        .await
+        .map_err(to_write_err)?;
+
+    let already_downloaded = if append { resume_from } else { 0 };
+    let progress_bar = ProgressBar::new(
+        response
+            .content_length()
+            .map(|len| len + already_downloaded)
+            .unwrap_or_default(),
+    );
+    progress_bar.set_style(
+        ProgressStyle::default_bar()
+            .template("{msg} {bar:40.cyan/blue} {bytes}/{total_bytes} ({eta})")
+            .unwrap(),
+    );
+    progress_bar.set_message(asset_name.to_string());
+    progress_bar.set_position(already_downloaded);
+
+    while let Some(chunk) = response.chunk().await? {
+        out_file.write_all(&chunk).await.map_err(to_write_err)?;
+        progress_bar.inc(chunk.len() as u64);
+    }
+    out_file.flush().await.map_err(to_write_err)?;
+    progress_bar.finish_and_clear();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    #[test_case("https://gitlab.example.com/group/tool/-/releases/v1.0.0/downloads/tool.tar.gz", true ; "same host")]
+    #[test_case("https://GITLAB.example.com:443/uploads/tool.tar.gz", true ; "same host and default port")]
+    #[test_case("https://gitlab.example.com:8443/tool.tar.gz", false ; "other port")]
+    #[test_case("http://gitlab.example.com/tool.tar.gz", false ; "other scheme port")]
+    #[test_case("https://objects.example.com/tool.tar.gz", false ; "other host")]
+    #[test_case("https://gitlab.example.com.attacker.example/tool.tar.gz", false ; "host suffix")]
+    fn applies_to_tests(url: &'_ str, expected: bool) {
+        let auth = DownloadAuth {
+            base_url: reqwest::Url::parse("https://gitlab.example.com/").unwrap(),
+            header: "PRIVATE-TOKEN",
+            value: "secret".to_string(),
+        };
+        assert_eq!(
+            auth.applies_to(&reqwest::Url::parse(url).unwrap()),
+            expected
+        )
+    }
+}
@@ -1,6 +1,5 @@
-use crate::{archiver, dirs, download, tool::Tool, version::Version};
+use crate::{archiver, dirs, lockfile::Lockfile, system::System, tool::Tool, version::Version};
 use async_std::fs::read_to_string;
-use octocrab::models::repos::Asset;
 use serde::{Deserialize, Serialize};
 use serde_json::{from_str, to_string_pretty};
 use std::path::{Path, PathBuf};
@@ -27,6 +26,8 @@ pub enum EnvironmentLoadError {
         msg: String,
         // source: Box<dyn serde::de::Error>,
     },
+    #[error(transparent)]
+    Lockfile(#[from] crate::lockfile::LockfileError),
 }
 
 #[derive(Debug, Error)]
@@ -36,11 +37,23 @@ pub enum EnvironmentError {
         expected_file_name: String,
         search_base_path: std::path::PathBuf,
     },
-    #[error("Failed to download file '{asset_name}' from '{asset_uri}'")]
-    AssetDownloadError {
-        asset_uri: reqwest::Url,
-        asset_name: String,
+    #[error("Failed to create the directory '{path}'. {source}")]
+    CreateDirectory {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to copy '{file_path}'. {source}")]
+    FileCopyError {
+        file_path: std::path::PathBuf,
+        source: std::io::Error,
     },
+    #[error("Failed to make '{file_path}' executable. {source}")]
+    SetPermissions {
+        file_path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Unable to install '{file_path}', it could not be extracted and is not an executable")]
+    UnsupportedAsset { file_path: std::path::PathBuf },
 }
 
 type Result<T, E = EnvironmentLoadError> = std::result::Result<T, E>;
@@ -48,10 +61,22 @@ type Result<T, E = EnvironmentLoadError> = std::result::Result<T, E>;
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Environment {
     pub name: String,
+    /// Directory the tools are installed in, it depends on where vers keeps its data so it is set
+    /// when the environment is loaded instead of being written into the committed file
+    #[serde(skip)]
     pub base_dir: String,
     // #[serde(skip)]
     // base_dir_path: PathBuf,
     pub tools: Vec<Tool>,
+    /// Platform the tools are installed for, as `os/arch`, when it isn't the machine vers runs on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    /// C library the tools are selected for on Linux, `glibc` or `musl`, instead of the detected one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub libc: Option<String>,
+    /// Resolved assets for the tools, stored in the `vers.lock` next to the environment file
+    #[serde(skip)]
+    pub lock: Lockfile,
 }
 
 impl Drop for Environment {
@@ -67,6 +92,15 @@ impl Drop for Environment {
             },
             Err(e) => error!("Failed to marshal Environment to JSON. {}", e),
         }
+
+        let lock_file = self.lock_file_path();
+        if self.lock.tools.is_empty() && !lock_file.exists() {
+            return;
+        }
+        match self.lock.save(&lock_file, &self.name) {
+            Ok(_) => debug!("Wrote lock file {:?}", lock_file),
+            Err(e) => error!("Failed to write lock file; {}", e),
+        }
     }
 }
 
@@ -86,6 +120,7 @@ impl Environment {
             }
         }
         let env_path = env_dir.join(format!("{}.json", name));
+        let lock = Lockfile::load(&dirs::get_lock_file_path(&config_dir), name)?;
         match read_to_string(&env_path).await {
             Ok(file_contents) => match from_str::<Self>(&file_contents) {
                 Ok(mut res) => {
@@ -94,6 +129,7 @@ impl Environment {
                         .to_str()
                         .expect("Unable to convert path to a string")
                         .to_string();
+                    res.lock = lock;
                     Ok(res)
                 }
                 Err(serde_err) => Err(EnvironmentLoadError::DeserializationError {
@@ -108,9 +144,12 @@ impl Environment {
                     let base_dir: String = env_dir.join(name).to_str().unwrap_or_default().into();
                     Ok(Environment {
                         name: name.to_string(),
-                        base_dir: base_dir.clone(),
+                        base_dir,
                         //base_dir_path: Path::new(&base_dir).to_path_buf(),
                         tools: Vec::new(),
+                        platform: None,
+                        libc: None,
+                        lock,
                     })
                 }
                 _ => Err(EnvironmentLoadError::FileReadError {
@@ -121,114 +160,271 @@ impl Environment {
         }
     }
 
+    /// Whether the tools are installed for another machine than the one vers runs on, they can't
+    /// be run while installing them.
+    pub fn is_foreign(&self) -> bool {
+        self.platform
+            .as_ref()
+            .and_then(|platform| platform.parse::<System>().ok())
+            .map_or(false, |system| !system.is_host())
+    }
+
+    pub fn lock_file_path(&self) -> PathBuf {
+        Path::new(&self.base_dir)
+            .parent()
+            .unwrap()
+            .join("vers.lock")
+    }
+
+    /// Whether the tool is linked into the environment at the provided version.
+    pub fn is_tool_installed(&self, tool: &'_ Tool, version: &'_ Version) -> bool {
+        let env_base_path = Path::new(&self.base_dir);
+        let tool_version_dir =
+            dirs::get_tool_version_download_dir(env_base_path, &tool.name, &version.as_tag());
+        let main_link = match tool.links().into_iter().next() {
+            Some(binary) => binary.link,
+            None => return false,
+        };
+        match std::fs::read_link(dirs::get_tool_link_path(env_base_path, &main_link)) {
+            Ok(link_target) => link_target.starts_with(tool_version_dir) && link_target.exists(),
+            Err(_) => false,
+        }
+    }
+
     pub async fn add_tool(
         &mut self,
-        name: &'_ str,
-        alias: &'_ str,
+        tool: &'_ Tool,
         version: Version,
-        asset: Asset,
-        asset_pattern: &'_ str,
-        file_pattern: &'_ str,
+        asset_path: &'_ Path,
     ) -> std::result::Result<(), EnvironmentError> {
         let env_base_path = Path::new(&self.base_dir);
-        let tool_dir = dirs::get_tool_download_dir(env_base_path, name);
+        let tool_dir = dirs::get_tool_download_dir(env_base_path, &tool.name);
         info!("Actual tools dir: {:?}", tool_dir);
 
-        let version_tag = version.as_tag();
         let tool_version_dir =
-            dirs::get_tool_version_download_dir(env_base_path, name, &version_tag);
-
-        match download::download_asset(&asset, &tool_version_dir).await {
-            Ok(asset_path) => {
-                info!("Completed downloading {}", asset.browser_download_url);
-                let symlink_dest = dirs::get_tool_link_path(env_base_path, alias);
-                let possible_extractors = archiver::determine_possible_extractors(&asset_path);
-
-                for extractor in possible_extractors {
-                    let extractor_name = &extractor.name();
-                    match archiver::handle_file_extraction(
-                        extractor,
-                        &asset_path,
-                        Some(tool_version_dir.clone()),
-                    )
-                    .await
-                    {
-                        Ok(_) => {
-                            info!(
-                                "Successfully extracted '{}' using the '{}' extractor",
-                                &asset_path.display(),
-                                extractor_name
-                            );
-                            let binary_file_name = if !file_pattern.is_empty() {
-                                file_pattern
-                            } else {
-                                alias
-                            };
-                            if let Some(bin_file) = find_binary(&tool_version_dir, binary_file_name)
-                            {
-                                create_symlink(&bin_file.into_path(), &symlink_dest);
-                                match self.tools.iter_mut().find(|t| t.name == name) {
-                                    // add to the tools list
-                                    Some(installed_tool) => {
-                                        installed_tool.set_current_version(&version);
-                                        let version_tag = &version.as_tag();
-                                        if !installed_tool
-                                            .installed_versions
-                                            .iter()
-                                            .any(|v| v[..] == version_tag[..])
-                                        {
-                                            installed_tool
-                                                .installed_versions
-                                                .push(version.as_tag());
-                                            info!(
-                                                "Added new version {} of {} in environment {}",
-                                                version.as_tag(),
-                                                name,
-                                                self.name
-                                            );
-                                        }
-                                        return Ok(());
-                                    }
-                                    // create a new tool, and add to our list
-                                    None => {
-                                        self.tools.push(Tool::new(
-                                            name,
-                                            alias,
-                                            &version,
-                                            asset_pattern,
-                                            file_pattern,
-                                        ));
-                                        info!(
-                                            "Added new tool {} in environment {}",
-                                            name, self.name
-                                        );
-                                        return Ok(());
-                                    }
-                                };
-                            } else {
-                                return Err(EnvironmentError::UnableToFindBinaryError {
-                                    expected_file_name: binary_file_name.to_string(),
-                                    search_base_path: tool_version_dir,
-                                });
-                            }
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to extract using '{}' Error: {:?}",
-                                extractor_name, e,
-                            );
-                        }
+            dirs::get_tool_version_download_dir(env_base_path, &tool.name, &version.as_tag());
+        // assets installed from a local file weren't downloaded into the version directory
+        std::fs::create_dir_all(&tool_version_dir).map_err(|source| {
+            EnvironmentError::CreateDirectory {
+                path: tool_version_dir.clone(),
+                source,
+            }
+        })?;
+        for extractor in archiver::determine_possible_extractors(asset_path) {
+            let extractor_name = &extractor.name();
+            let format = extractor.format();
+            match archiver::handle_file_extraction(
+                extractor,
+                asset_path,
+                Some(tool_version_dir.clone()),
+            )
+            .await
+            {
+                Ok(_) => {
+                    info!(
+                        "Successfully extracted '{}' using the '{}' extractor",
+                        &asset_path.display(),
+                        extractor_name
+                    );
+                    if format == archiver::ArchiveFormat::AppImage {
+                        let entry_point = tool_version_dir.join(archiver::APP_IMAGE_ENTRY_POINT);
+                        return self.link_binary(tool, version, &entry_point);
                     }
+                    return self.link_tool(tool, version, &tool_version_dir);
                 }
+                Err(e) => {
+                    error!(
+                        "Failed to extract using '{}' Error: {:?}",
+                        extractor_name, e,
+                    );
+                }
+            }
+        }
+
+        if is_executable(asset_path) {
+            info!("'{}' is an executable", &asset_path.display());
+            return self.add_executable(tool, version, asset_path, &tool_version_dir);
+        }
+        Err(EnvironmentError::UnsupportedAsset {
+            file_path: asset_path.to_path_buf(),
+        })
+    }
+
+    /// Add a tool released as a bare executable, it is renamed to the binary name of the tool so
+    /// it can be found like the binaries extracted from archives.
+    fn add_executable(
+        &mut self,
+        tool: &'_ Tool,
+        version: Version,
+        asset_path: &'_ Path,
+        tool_version_dir: &'_ Path,
+    ) -> std::result::Result<(), EnvironmentError> {
+        let binary_file_name = if !tool.file_pattern.is_empty() {
+            &tool.file_pattern
+        } else {
+            &tool.alias
+        };
+        let bin_file = tool_version_dir.join(binary_file_name);
+        if asset_path != bin_file {
+            // downloads are moved, files installed from elsewhere on disk are left untouched
+            let moved = if asset_path.parent() == Some(tool_version_dir) {
+                std::fs::rename(asset_path, &bin_file)
+            } else {
+                std::fs::copy(asset_path, &bin_file).map(|_| ())
+            };
+            moved.map_err(|source| EnvironmentError::FileCopyError {
+                file_path: asset_path.to_path_buf(),
+                source,
+            })?;
+        }
+        set_executable(&bin_file)?;
+        self.link_tool(tool, version, tool_version_dir)
+    }
+
+    /// Add a tool whose files are in a directory on disk, the directory is copied so the tool
+    /// keeps working when the original is removed.
+    pub async fn add_tool_from_dir(
+        &mut self,
+        tool: &'_ Tool,
+        version: Version,
+        source_dir: &'_ Path,
+    ) -> std::result::Result<(), EnvironmentError> {
+        let tool_version_dir = dirs::get_tool_version_download_dir(
+            Path::new(&self.base_dir),
+            &tool.name,
+            &version.as_tag(),
+        );
+        copy_dir(source_dir, &tool_version_dir)?;
+        self.link_tool(tool, version, &tool_version_dir)
+    }
+
+    /// Add a tool a toolchain already installed into its version directory, `bin_dir` is where
+    /// the toolchain put its entry points.
+    pub fn add_installed_tool(
+        &mut self,
+        tool: &'_ Tool,
+        version: Version,
+        bin_dir: &'_ Path,
+    ) -> std::result::Result<(), EnvironmentError> {
+        self.link_tool(tool, version, bin_dir)
+    }
 
-                Ok(())
+    /// Link the binaries of a tool found in its version directory into the environment and record
+    /// the tool.
+    fn link_tool(
+        &mut self,
+        tool: &'_ Tool,
+        version: Version,
+        tool_version_dir: &'_ Path,
+    ) -> std::result::Result<(), EnvironmentError> {
+        // every binary has to be found before any of them is linked
+        let bin_files = tool
+            .links()
+            .into_iter()
+            .map(
+                |binary| match find_binary(tool_version_dir, &binary.file_pattern) {
+                    Some(bin_file) => Ok((bin_file.into_path(), binary.link)),
+                    None => Err(EnvironmentError::UnableToFindBinaryError {
+                        expected_file_name: binary.file_pattern,
+                        search_base_path: tool_version_dir.to_path_buf(),
+                    }),
+                },
+            )
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for (bin_file, link) in &bin_files {
+            self.link_file(bin_file, link)?;
+        }
+        self.record_tool(tool, version);
+        Ok(())
+    }
+
+    /// Link the main binary of a tool into the environment and record the tool, for tools whose
+    /// entry point isn't named after the tool.
+    fn link_binary(
+        &mut self,
+        tool: &'_ Tool,
+        version: Version,
+        bin_file: &'_ Path,
+    ) -> std::result::Result<(), EnvironmentError> {
+        self.link_file(bin_file, &tool.alias)?;
+        self.record_tool(tool, version);
+        Ok(())
+    }
+
+    fn link_file(
+        &self,
+        bin_file: &'_ Path,
+        link: &'_ str,
+    ) -> std::result::Result<(), EnvironmentError> {
+        let symlink_dest = dirs::get_tool_link_path(Path::new(&self.base_dir), link);
+        // single compressed files are decompressed without the permissions of the original
+        if !is_executable::is_executable(bin_file) && bin_file.is_file() {
+            set_executable(bin_file)?;
+        }
+        create_symlink(bin_file, &symlink_dest);
+        Ok(())
+    }
+
+    fn record_tool(&mut self, tool: &'_ Tool, version: Version) {
+        let name = tool.name.as_str();
+        match self.tools.iter_mut().find(|t| t.name == name) {
+            // add to the tools list
+            Some(installed_tool) => {
+                installed_tool.set_current_version(&version);
+                installed_tool.verify = tool.verify.clone();
+                installed_tool.base_url = tool.base_url.clone();
+                installed_tool.template = tool.template.clone();
+                installed_tool.from_file = tool.from_file.clone();
+                installed_tool.fallback = tool.fallback.clone();
+                installed_tool.binaries = tool.binaries.clone();
+                installed_tool.entry_points = tool.entry_points.clone();
+                installed_tool.version_req = tool.version_req.clone();
+                let version_tag = &version.as_tag();
+                if !installed_tool
+                    .installed_versions
+                    .iter()
+                    .any(|v| v[..] == version_tag[..])
+                {
+                    installed_tool.installed_versions.push(version.as_tag());
+                    info!(
+                        "Added new version {} of {} in environment {}",
+                        version.as_tag(),
+                        name,
+                        self.name
+                    );
+                }
+            }
+            // create a new tool, and add to our list
+            None => {
+                self.tools.push(Tool {
+                    current_version: version.as_tag(),
+                    installed_versions: vec![version.as_tag()],
+                    ..tool.clone()
+                });
+                info!("Added new tool {} in environment {}", name, self.name);
             }
-            Err(_) => Err(EnvironmentError::AssetDownloadError {
-                asset_uri: asset.browser_download_url,
-                asset_name: asset.name,
-            }),
+        };
+    }
+}
+
+fn copy_dir(source_dir: &'_ Path, out_dir: &'_ Path) -> std::result::Result<(), EnvironmentError> {
+    let to_copy_err = |file_path: &Path, source| EnvironmentError::FileCopyError {
+        file_path: file_path.to_path_buf(),
+        source,
+    };
+    for entry in WalkDir::new(source_dir).into_iter().filter_map(Result::ok) {
+        let relative_path = entry
+            .path()
+            .strip_prefix(source_dir)
+            .expect("walked entries are inside the source directory");
+        let out_path = out_dir.join(relative_path);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&out_path).map_err(|e| to_copy_err(&out_path, e))?;
+        } else {
+            std::fs::copy(entry.path(), &out_path).map_err(|e| to_copy_err(entry.path(), e))?;
         }
     }
+    Ok(())
 }
 
 fn create_symlink(src: &'_ Path, dest: &'_ Path) {
@@ -262,9 +458,83 @@ fn create_symlink(src: &'_ Path, dest: &'_ Path) {
     }
 }
 
+/// Magic numbers of files that can be run without being extracted: ELF, Mach-O in both byte
+/// orders, universal Mach-O binaries and scripts with a shebang.
+const EXECUTABLE_MAGIC: [&[u8]; 7] = [
+    b"\x7fELF",
+    &[0xfe, 0xed, 0xfa, 0xce],
+    &[0xfe, 0xed, 0xfa, 0xcf],
+    &[0xce, 0xfa, 0xed, 0xfe],
+    &[0xcf, 0xfa, 0xed, 0xfe],
+    &[0xca, 0xfe, 0xba, 0xbe],
+    b"#!",
+];
+
+fn is_executable_header(header: &'_ [u8]) -> bool {
+    EXECUTABLE_MAGIC
+        .iter()
+        .any(|magic| header.starts_with(magic))
+}
+
+fn is_executable(file_path: &'_ Path) -> bool {
+    use std::io::Read;
+
+    let mut header = Vec::with_capacity(4);
+    match std::fs::File::open(file_path).and_then(|file| file.take(4).read_to_end(&mut header)) {
+        Ok(_) => is_executable_header(&header),
+        Err(read_err) => {
+            debug!("Unable to read '{}'. {read_err}", file_path.display());
+            false
+        }
+    }
+}
+
+fn set_executable(file_path: &'_ Path) -> std::result::Result<(), EnvironmentError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::set_permissions(file_path, std::fs::Permissions::from_mode(0o755)).map_err(|source| {
+        EnvironmentError::SetPermissions {
+            file_path: file_path.to_path_buf(),
+            source,
+        }
+    })
+}
+
 fn find_binary(folder: &'_ Path, bin_name: &'_ str) -> Option<DirEntry> {
     WalkDir::new(folder)
         .into_iter()
         .filter_map(Result::ok)
-        .find(|entry| entry.file_name() == bin_name)
+        // packages have documentation directories named after the tool, like `usr/share/doc/bat`
+        .find(|entry| entry.file_name() == bin_name && !entry.file_type().is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    #[test_case(b"\x7fELF\x02\x01\x01", true ; "elf")]
+    #[test_case(&[0xcf, 0xfa, 0xed, 0xfe, 0x0c], true ; "mach-o 64 bit")]
+    #[test_case(&[0xca, 0xfe, 0xba, 0xbe], true ; "universal mach-o")]
+    #[test_case(b"#!/bin/sh\n", true ; "script")]
+    #[test_case(&[0x1f, 0x8b, 0x08, 0x00], false ; "gzip")]
+    #[test_case(b"PK\x03\x04", false ; "zip")]
+    #[test_case(b"", false ; "empty")]
+    fn is_executable_header_tests(header: &'_ [u8], expected: bool) {
+        assert_eq!(is_executable_header(header), expected)
+    }
+
+    #[test]
+    fn base_dir_is_not_written() {
+        // files written before the base directory was skipped still name one
+        let env = from_str::<Environment>(
+            r#"{"name": "global", "base_dir": "/home/user/.vers/envs/global", "tools": []}"#,
+        )
+        .unwrap();
+        let contents = to_string_pretty(&env).unwrap();
+        let base_dir = env.base_dir.to_string();
+        // dropping the environment writes it next to its base directory
+        std::mem::forget(env);
+        assert_eq!(base_dir, "");
+        assert!(!contents.contains("base_dir"));
+    }
 }
@@ -0,0 +1,307 @@
+use crate::{
+    download::DownloadAuth,
+    source::{self, Artifact, ToolSource},
+    version::Version,
+};
+use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Instance used when no other Gitea or Forgejo instance was configured.
+pub const DEFAULT_GITEA_URL: &str = "https://gitea.com";
+
+static INSTANCE: OnceCell<Gitea> = OnceCell::new();
+
+#[derive(Debug, Error)]
+pub enum GiteaError {
+    #[error("Invalid Gitea URL '{url}'. {message}")]
+    InvalidUrl { url: String, message: String },
+    #[error("Failed to get '{url}': {source}")]
+    Request {
+        url: reqwest::Url,
+        source: reqwest::Error,
+    },
+    #[error("Failed to get '{url}': server responded with {status}")]
+    HttpStatus {
+        url: reqwest::Url,
+        status: reqwest::StatusCode,
+    },
+    #[error("Failed to deserialize the response of '{url}'. {msg}")]
+    Deserialization { url: reqwest::Url, msg: String },
+}
+
+type Result<T, E = GiteaError> = std::result::Result<T, E>;
+
+/// Client for the releases API of a Gitea or Forgejo instance, the API mirrors the one of GitHub.
+#[derive(Debug, Clone)]
+pub struct Gitea {
+    base_url: reqwest::Url,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl Default for Gitea {
+    fn default() -> Self {
+        Self::new(DEFAULT_GITEA_URL, None).expect("the default Gitea URL is valid")
+    }
+}
+
+impl Gitea {
+    pub fn new(base_url: &'_ str, token: Option<String>) -> Result<Self> {
+        let to_url_err = |message: String| GiteaError::InvalidUrl {
+            url: base_url.to_string(),
+            message,
+        };
+        let base_url = reqwest::Url::parse(&format!("{}/", base_url.trim_end_matches('/')))
+            .map_err(|parse_err| to_url_err(parse_err.to_string()))?;
+        if base_url.cannot_be_a_base() {
+            return Err(to_url_err("not a base URL".to_string()));
+        }
+        Ok(Self {
+            base_url,
+            token,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    fn releases_url(&self, owner: &'_ str, repo: &'_ str, path: &'_ str) -> Result<reqwest::Url> {
+        let path = format!("api/v1/repos/{owner}/{repo}/releases{path}");
+        self.base_url
+            .join(&path)
+            .map_err(|parse_err| GiteaError::InvalidUrl {
+                url: path,
+                message: parse_err.to_string(),
+            })
+    }
+
+    /// The header authenticating downloads from the instance, when a token is configured for it.
+    fn download_auth(&self) -> Option<DownloadAuth> {
+        let token = self.token.as_ref()?;
+        Some(DownloadAuth {
+            base_url: self.base_url.clone(),
+            header: "Authorization",
+            value: format!("token {token}"),
+        })
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, url: reqwest::Url) -> Result<T> {
+        let to_request_err = |source| GiteaError::Request {
+            url: url.clone(),
+            source,
+        };
+        let mut request = self.client.get(url.as_str());
+        if let Some(token) = &self.token {
+            request = request.header(reqwest::header::AUTHORIZATION, format!("token {token}"));
+        }
+        let response = request.send().await.map_err(to_request_err)?;
+        if !response.status().is_success() {
+            return Err(GiteaError::HttpStatus {
+                url,
+                status: response.status(),
+            });
+        }
+        let contents = response.text().await.map_err(to_request_err)?;
+        serde_json::from_str(&contents).map_err(|de_err| GiteaError::Deserialization {
+            url,
+            msg: de_err.to_string(),
+        })
+    }
+}
+
+/// Use a configured Gitea instance for every request made by this process.
+pub fn initialise(gitea: Gitea) {
+    if INSTANCE.set(gitea).is_err() {
+        warn!("The Gitea client was already initialised");
+    }
+}
+
+pub fn instance() -> &'static Gitea {
+    INSTANCE.get_or_init(Gitea::default)
+}
+
+/// The client for a tool, tools hosted on another instance get a client of their own.
+fn client(base_url: Option<&'_ str>) -> Result<Gitea> {
+    client_for(instance(), base_url)
+}
+
+fn client_for(instance: &'_ Gitea, base_url: Option<&'_ str>) -> Result<Gitea> {
+    match base_url {
+        Some(base_url) => Gitea::new(
+            base_url,
+            source::token_for(
+                base_url,
+                instance.base_url.as_str(),
+                instance.token.as_deref(),
+            ),
+        ),
+        None => Ok(instance.clone()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaRelease {
+    tag_name: String,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    assets: Vec<GiteaAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl GiteaRelease {
+    fn into_artifacts(self) -> Vec<Artifact> {
+        self.assets
+            .into_iter()
+            .filter_map(
+                |asset| match reqwest::Url::parse(&asset.browser_download_url) {
+                    Ok(url) => Some(Artifact {
+                        name: asset.name,
+                        url,
+                    }),
+                    Err(parse_err) => {
+                        warn!(
+                            "Skipping asset {} with invalid URL {}. {parse_err}",
+                            asset.name, asset.browser_download_url
+                        );
+                        None
+                    }
+                },
+            )
+            .collect()
+    }
+}
+
+/// Releases of a repository on a Gitea or Forgejo instance.
+#[derive(Debug)]
+pub struct GiteaSource {
+    pub owner: String,
+    pub repo: String,
+    pub base_url: Option<String>,
+}
+
+impl GiteaSource {
+    async fn get_release(&self, path: &'_ str) -> Result<GiteaRelease> {
+        let gitea = client(self.base_url.as_deref())?;
+        gitea
+            .get(gitea.releases_url(&self.owner, &self.repo, path)?)
+            .await
+    }
+}
+
+#[async_trait]
+impl ToolSource for GiteaSource {
+    fn default_alias(&self) -> &str {
+        &self.repo
+    }
+
+    async fn list_versions(&self, pre_release: bool) -> source::Result<Vec<String>> {
+        self.list_versions_page(pre_release, 1).await
+    }
+
+    async fn list_versions_page(
+        &self,
+        pre_release: bool,
+        page: u32,
+    ) -> source::Result<Vec<String>> {
+        let gitea = client(self.base_url.as_deref())?;
+        let path = format!("?limit=50&page={page}");
+        Ok(gitea
+            .get::<Vec<GiteaRelease>>(gitea.releases_url(&self.owner, &self.repo, &path)?)
+            .await?
+            .into_iter()
+            .filter(|release| pre_release || !release.prerelease)
+            .map(|release| release.tag_name)
+            .collect())
+    }
+
+    async fn resolve_version(&self, version: &'_ Version) -> source::Result<String> {
+        info!(
+            "Getting release({}) for {}/{}",
+            version.as_tag(),
+            &self.owner,
+            &self.repo
+        );
+        if version == &Version::Latest {
+            return Ok(self.get_release("/latest").await?.tag_name);
+        }
+
+        let tag = version.as_tag();
+        match self.get_release(&format!("/tags/{tag}")).await {
+            Ok(tagged_release) => Ok(tagged_release.tag_name),
+            Err(_) => Ok(self.get_release(&format!("/tags/v{tag}")).await?.tag_name),
+        }
+    }
+
+    async fn list_artifacts(&self, tag: &'_ str) -> source::Result<Vec<Artifact>> {
+        Ok(self
+            .get_release(&format!("/tags/{tag}"))
+            .await?
+            .into_artifacts())
+    }
+
+    fn download_auth(&self) -> Option<DownloadAuth> {
+        client(self.base_url.as_deref()).ok()?.download_auth()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    #[test_case("https://codeberg.org", "/latest", "https://codeberg.org/api/v1/repos/forgejo/forgejo/releases/latest" ; "latest")]
+    #[test_case("https://git.example.com/forge/", "/tags/v1.0.0", "https://git.example.com/forge/api/v1/repos/forgejo/forgejo/releases/tags/v1.0.0" ; "instance with a path")]
+    fn releases_url_tests(base_url: &'_ str, path: &'_ str, expected: &'_ str) {
+        let gitea = Gitea::new(base_url, None).unwrap();
+        assert_eq!(
+            gitea
+                .releases_url("forgejo", "forgejo", path)
+                .unwrap()
+                .as_str(),
+            expected
+        )
+    }
+
+    #[test_case("https://git.example.com/", Some("secret") ; "configured instance")]
+    #[test_case("https://attacker.example", None ; "foreign instance")]
+    fn client_token_tests(base_url: &'_ str, expected: Option<&'_ str>) {
+        let instance = Gitea::new("https://git.example.com", Some("secret".to_string())).unwrap();
+        assert_eq!(
+            client_for(&instance, Some(base_url)).unwrap().token,
+            expected.map(String::from)
+        )
+    }
+
+    #[test]
+    fn release_from_api_response() {
+        let release = serde_json::from_str::<GiteaRelease>(
+            r#"{
+                "id": 1,
+                "tag_name": "v1.2.0",
+                "name": "v1.2.0",
+                "draft": false,
+                "prerelease": true,
+                "assets": [
+                    {
+                        "id": 7,
+                        "name": "tea-1.2.0-linux-amd64",
+                        "size": 1024,
+                        "browser_download_url": "https://gitea.com/gitea/tea/releases/download/v1.2.0/tea-1.2.0-linux-amd64"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(release.tag_name, "v1.2.0");
+        assert!(release.prerelease);
+        let artifacts = release.into_artifacts();
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].name, "tea-1.2.0-linux-amd64");
+    }
+}
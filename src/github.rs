@@ -1,13 +1,23 @@
 use crate::{
-    system::System,
-    version::{parse_version, Version},
+    download::DownloadAuth,
+    source::{self, Artifact, ToolSource},
+    version::Version,
 };
-use octocrab::models::repos::{Asset, Release};
-use regex::Regex;
-use skim::prelude::*;
-use std::io::Cursor;
+use async_trait::async_trait;
+use octocrab::{models::repos::Release, Octocrab};
+use once_cell::sync::OnceCell;
+use std::sync::Arc;
 use thiserror::Error;
-use tracing::{debug, info};
+use tracing::info;
+
+/// API used when no GitHub Enterprise Server was configured.
+const DEFAULT_GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Token of the configured GitHub instance, the clients of tools hosted on the same instance use
+/// it too.
+static TOKEN: OnceCell<String> = OnceCell::new();
+/// URL of the configured GitHub instance.
+static BASE_URL: OnceCell<String> = OnceCell::new();
 
 #[derive(Debug, Error)]
 pub enum GitHubError {
@@ -17,16 +27,79 @@ pub enum GitHubError {
 
 type Result<T, E = GitHubError> = std::result::Result<T, E>;
 
+/// Configure the client used for every GitHub request, `base_url` points to a GitHub Enterprise
+/// Server instead of github.com.
+pub fn initialise(base_url: Option<&'_ str>, token: Option<String>) -> Result<()> {
+    if let Some(token) = &token {
+        let _ = TOKEN.set(token.to_string());
+    }
+    if let Some(base_url) = base_url {
+        let _ = BASE_URL.set(base_url.to_string());
+    }
+    if base_url.is_some() || token.is_some() {
+        octocrab::initialise(build_client(base_url, token)?);
+    }
+    Ok(())
+}
+
+fn build_client(base_url: Option<&'_ str>, token: Option<String>) -> Result<Octocrab> {
+    let mut builder = Octocrab::builder();
+    if let Some(token) = token {
+        builder = builder.personal_token(token);
+    }
+    if let Some(base_url) = base_url {
+        builder = builder.base_uri(enterprise_api_url(base_url))?;
+    }
+    Ok(builder.build()?)
+}
+
+/// The REST API of a GitHub Enterprise Server is served under `/api/v3`.
+fn enterprise_api_url(base_url: &'_ str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    if base_url.ends_with("/api/v3") || base_url.starts_with("https://api.github.com") {
+        format!("{base_url}/")
+    } else {
+        format!("{base_url}/api/v3/")
+    }
+}
+
+/// The client for a tool, tools hosted on another GitHub instance get a client of their own.
+fn client(base_url: Option<&'_ str>) -> Result<Arc<Octocrab>> {
+    match base_url {
+        Some(base_url) => Ok(Arc::new(build_client(
+            Some(base_url),
+            token_for(base_url, BASE_URL.get().map(String::as_str), TOKEN.get()),
+        )?)),
+        None => Ok(octocrab::instance()),
+    }
+}
+
+/// The token for a tool's GitHub instance, see [source::token_for].
+fn token_for(
+    base_url: &'_ str,
+    configured_url: Option<&'_ str>,
+    configured_token: Option<&'_ String>,
+) -> Option<String> {
+    source::token_for(
+        base_url,
+        configured_url.unwrap_or(DEFAULT_GITHUB_API_URL),
+        configured_token.map(String::as_str),
+    )
+}
+
 pub async fn get_repo_releases(
+    base_url: Option<&'_ str>,
     owner: &'_ str,
     repo: &'_ str,
     pre_release: bool,
+    page: u32,
 ) -> Result<Vec<String>> {
-    Ok(octocrab::instance()
+    Ok(client(base_url)?
         .repos(owner, repo)
         .releases()
         .list()
         .per_page(100)
+        .page(page)
         .send()
         .await?
         .items
@@ -47,6 +120,7 @@ pub async fn get_repo_releases(
 }
 
 pub async fn get_specific_release_for_repo(
+    base_url: Option<&'_ str>,
     owner: &'_ str,
     repo: &'_ str,
     version: &'_ Version,
@@ -57,7 +131,7 @@ pub async fn get_specific_release_for_repo(
         owner,
         repo
     );
-    let octo = octocrab::instance();
+    let octo = client(base_url)?;
     if version == &Version::Latest {
         match octo.repos(owner, repo).releases().get_latest().await {
             Ok(latest_release) => Ok(latest_release),
@@ -86,80 +160,116 @@ pub async fn get_specific_release_for_repo(
     }
 }
 
-pub async fn get_latest_release_tag(owner: &'_ str, repo: &'_ str) -> Option<Version> {
-    match octocrab::instance()
+pub async fn get_release_by_tag(
+    base_url: Option<&'_ str>,
+    owner: &'_ str,
+    repo: &'_ str,
+    tag: &'_ str,
+) -> Result<Release> {
+    Ok(client(base_url)?
         .repos(owner, repo)
         .releases()
-        .get_latest()
-        .await
-    {
-        Ok(tag) => Some(parse_version(&tag.tag_name)),
-        Err(_) => None,
-    }
+        .get_by_tag(tag)
+        .await?)
 }
 
-pub fn get_platform_specific_asset(
-    release: &'_ Release,
-    system: &'_ System,
-    user_pattern: &'_ str,
-) -> Option<Asset> {
-    let platform_assets: Vec<Asset> = release
-        .assets
-        .iter()
-        .filter_map(|asset| {
-            if !user_pattern.is_empty() {
-                let r = Regex::new(user_pattern).unwrap_or_else(|_| {
-                    panic!("{} is not a valid Regular Expression", user_pattern)
-                });
-                debug!("Matching '{}' against '{}'", r.as_str(), &asset.name);
-                if r.is_match(&asset.name) {
-                    Some(asset.clone())
-                } else {
-                    None
-                }
-            } else if system.is_match(&asset.name) {
-                debug!("Asset info: {:?}", asset.name);
-                Some(asset.clone())
-            } else {
-                None
-            }
+/// Releases of a repository on github.com or a GitHub Enterprise Server.
+#[derive(Debug)]
+pub struct GitHubSource {
+    pub owner: String,
+    pub repo: String,
+    pub base_url: Option<String>,
+}
+
+#[async_trait]
+impl ToolSource for GitHubSource {
+    fn default_alias(&self) -> &str {
+        &self.repo
+    }
+
+    async fn list_versions(&self, pre_release: bool) -> source::Result<Vec<String>> {
+        self.list_versions_page(pre_release, 1).await
+    }
+
+    async fn list_versions_page(
+        &self,
+        pre_release: bool,
+        page: u32,
+    ) -> source::Result<Vec<String>> {
+        Ok(get_repo_releases(
+            self.base_url.as_deref(),
+            &self.owner,
+            &self.repo,
+            pre_release,
+            page,
+        )
+        .await?)
+    }
+
+    async fn resolve_version(&self, version: &'_ Version) -> source::Result<String> {
+        Ok(get_specific_release_for_repo(
+            self.base_url.as_deref(),
+            &self.owner,
+            &self.repo,
+            version,
+        )
+        .await?
+        .tag_name)
+    }
+
+    async fn list_artifacts(&self, tag: &'_ str) -> source::Result<Vec<Artifact>> {
+        Ok(
+            get_release_by_tag(self.base_url.as_deref(), &self.owner, &self.repo, tag)
+                .await?
+                .assets
+                .into_iter()
+                .map(|asset| Artifact {
+                    name: asset.name,
+                    url: asset.browser_download_url,
+                })
+                .collect(),
+        )
+    }
+
+    fn download_auth(&self) -> Option<DownloadAuth> {
+        let configured_url = BASE_URL.get().map(String::as_str);
+        let base_url = self
+            .base_url
+            .as_deref()
+            .or(configured_url)
+            .unwrap_or(DEFAULT_GITHUB_API_URL);
+        let token = token_for(base_url, configured_url, TOKEN.get())?;
+        Some(DownloadAuth {
+            base_url: reqwest::Url::parse(base_url).ok()?,
+            header: "Authorization",
+            value: format!("token {token}"),
         })
-        .collect();
-    match &platform_assets.len() {
-        2.. => {
-            // if we get multiple results for the platform show the user a selector
-            let item_reader = SkimItemReader::default().of_bufread(Cursor::new(
-                platform_assets
-                    .iter()
-                    .map(|a| a.name.to_string())
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-            ));
-            let selected_item: Vec<Asset> = Skim::run_with(
-                &SkimOptionsBuilder::default()
-                    .height(Some("75%"))
-                    .build()
-                    .unwrap(),
-                Some(item_reader),
-            )
-            .map(|items| {
-                items
-                    .selected_items
-                    .iter()
-                    .map(|item| {
-                        platform_assets
-                            .clone()
-                            .into_iter()
-                            .find(|asset| asset.name == item.text())
-                            .unwrap()
-                    })
-                    .collect()
-            })
-            .unwrap();
-            Some(selected_item.get(0).unwrap().to_owned())
-        }
-        // if we only get one result we can just return that as is
-        1 => Some(platform_assets.get(0).unwrap().clone()),
-        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    #[test_case("https://github.example.com", Some("https://github.example.com"), Some("secret") ; "configured server")]
+    #[test_case("https://api.github.com", None, Some("secret") ; "public api")]
+    #[test_case("https://attacker.example", None, None ; "foreign server")]
+    #[test_case("https://attacker.example", Some("https://github.example.com"), None ; "foreign server next to a configured one")]
+    fn token_for_tests(
+        base_url: &'_ str,
+        configured_url: Option<&'_ str>,
+        expected: Option<&'_ str>,
+    ) {
+        assert_eq!(
+            token_for(base_url, configured_url, Some(&"secret".to_string())),
+            expected.map(String::from)
+        )
+    }
+
+    #[test_case("https://github.example.com", "https://github.example.com/api/v3/" ; "server url")]
+    #[test_case("https://github.example.com/api/v3/", "https://github.example.com/api/v3/" ; "api url")]
+    #[test_case("https://api.github.com", "https://api.github.com/" ; "public api")]
+    fn enterprise_api_url_tests(base_url: &'_ str, expected: &'_ str) {
+        assert_eq!(enterprise_api_url(base_url), expected)
     }
 }
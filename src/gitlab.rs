@@ -0,0 +1,385 @@
+use crate::{
+    download::DownloadAuth,
+    source::{self, Artifact, SourceError, ToolSource},
+    version::Version,
+};
+use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// API used when no other GitLab instance was configured.
+pub const DEFAULT_GITLAB_URL: &str = "https://gitlab.com";
+
+static INSTANCE: OnceCell<GitLab> = OnceCell::new();
+
+#[derive(Debug, Error)]
+pub enum GitLabError {
+    #[error("Invalid GitLab URL '{url}'. {message}")]
+    InvalidUrl { url: String, message: String },
+    #[error("Failed to get '{url}': {source}")]
+    Request {
+        url: reqwest::Url,
+        source: reqwest::Error,
+    },
+    #[error("Failed to get '{url}': server responded with {status}")]
+    HttpStatus {
+        url: reqwest::Url,
+        status: reqwest::StatusCode,
+    },
+    #[error("Failed to deserialize the response of '{url}'. {msg}")]
+    Deserialization { url: reqwest::Url, msg: String },
+}
+
+type Result<T, E = GitLabError> = std::result::Result<T, E>;
+
+/// Client for the releases API of a GitLab instance.
+#[derive(Debug, Clone)]
+pub struct GitLab {
+    base_url: reqwest::Url,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl Default for GitLab {
+    fn default() -> Self {
+        Self::new(DEFAULT_GITLAB_URL, None).expect("the default GitLab URL is valid")
+    }
+}
+
+impl GitLab {
+    pub fn new(base_url: &'_ str, token: Option<String>) -> Result<Self> {
+        let to_url_err = |message: String| GitLabError::InvalidUrl {
+            url: base_url.to_string(),
+            message,
+        };
+        let base_url = reqwest::Url::parse(&format!("{}/", base_url.trim_end_matches('/')))
+            .map_err(|parse_err| to_url_err(parse_err.to_string()))?;
+        if base_url.cannot_be_a_base() {
+            return Err(to_url_err("not a base URL".to_string()));
+        }
+        Ok(Self {
+            base_url,
+            token,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    fn releases_url(&self, project: &'_ str, tag: Option<&'_ str>) -> Result<reqwest::Url> {
+        // project paths and tags are passed as a single URL encoded path segment
+        let encode = |s: &str| s.replace('%', "%25").replace('/', "%2F");
+        let path = match tag {
+            Some(tag) => format!(
+                "api/v4/projects/{}/releases/{}",
+                encode(project),
+                encode(tag)
+            ),
+            None => format!("api/v4/projects/{}/releases?per_page=100", encode(project)),
+        };
+        self.base_url
+            .join(&path)
+            .map_err(|parse_err| GitLabError::InvalidUrl {
+                url: path,
+                message: parse_err.to_string(),
+            })
+    }
+
+    /// The header authenticating downloads from the instance, when a token is configured for it.
+    fn download_auth(&self) -> Option<DownloadAuth> {
+        let token = self.token.as_ref()?;
+        Some(DownloadAuth {
+            base_url: self.base_url.clone(),
+            header: "PRIVATE-TOKEN",
+            value: token.to_string(),
+        })
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, url: reqwest::Url) -> Result<T> {
+        let to_request_err = |source| GitLabError::Request {
+            url: url.clone(),
+            source,
+        };
+        let mut request = self.client.get(url.as_str());
+        if let Some(token) = &self.token {
+            request = request.header("PRIVATE-TOKEN", token);
+        }
+        let response = request.send().await.map_err(to_request_err)?;
+        if !response.status().is_success() {
+            return Err(GitLabError::HttpStatus {
+                url,
+                status: response.status(),
+            });
+        }
+        let contents = response.text().await.map_err(to_request_err)?;
+        serde_json::from_str(&contents).map_err(|de_err| GitLabError::Deserialization {
+            url,
+            msg: de_err.to_string(),
+        })
+    }
+}
+
+/// Use a configured GitLab instance for every request made by this process.
+pub fn initialise(gitlab: GitLab) {
+    if INSTANCE.set(gitlab).is_err() {
+        warn!("The GitLab client was already initialised");
+    }
+}
+
+pub fn instance() -> &'static GitLab {
+    INSTANCE.get_or_init(GitLab::default)
+}
+
+/// The client for a tool, tools hosted on another GitLab instance get a client of their own.
+fn client(base_url: Option<&'_ str>) -> Result<GitLab> {
+    client_for(instance(), base_url)
+}
+
+fn client_for(instance: &'_ GitLab, base_url: Option<&'_ str>) -> Result<GitLab> {
+    match base_url {
+        Some(base_url) => GitLab::new(
+            base_url,
+            source::token_for(
+                base_url,
+                instance.base_url.as_str(),
+                instance.token.as_deref(),
+            ),
+        ),
+        None => Ok(instance.clone()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabRelease {
+    tag_name: String,
+    #[serde(default)]
+    upcoming_release: bool,
+    #[serde(default)]
+    assets: GitLabReleaseAssets,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GitLabReleaseAssets {
+    #[serde(default)]
+    links: Vec<GitLabReleaseLink>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabReleaseLink {
+    name: String,
+    url: String,
+    direct_asset_url: Option<String>,
+}
+
+impl GitLabRelease {
+    fn into_artifacts(self) -> Vec<Artifact> {
+        self.assets
+            .links
+            .into_iter()
+            .filter_map(|link| {
+                let url = link.direct_asset_url.unwrap_or(link.url);
+                match reqwest::Url::parse(&url) {
+                    Ok(url) => Some(Artifact {
+                        name: link.name,
+                        url,
+                    }),
+                    Err(parse_err) => {
+                        warn!(
+                            "Skipping asset {} with invalid URL {url}. {parse_err}",
+                            link.name
+                        );
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+async fn list_releases(
+    gitlab: &'_ GitLab,
+    project: &'_ str,
+    page: u32,
+) -> Result<Vec<GitLabRelease>> {
+    let mut url = gitlab.releases_url(project, None)?;
+    url.query_pairs_mut().append_pair("page", &page.to_string());
+    gitlab.get(url).await
+}
+
+async fn get_release_by_tag(
+    gitlab: &'_ GitLab,
+    project: &'_ str,
+    tag: &'_ str,
+) -> Result<GitLabRelease> {
+    gitlab.get(gitlab.releases_url(project, Some(tag))?).await
+}
+
+/// Releases of a project on gitlab.com or a self-hosted GitLab instance.
+#[derive(Debug)]
+pub struct GitLabSource {
+    pub project: String,
+    pub base_url: Option<String>,
+}
+
+#[async_trait]
+impl ToolSource for GitLabSource {
+    fn default_alias(&self) -> &str {
+        self.project.rsplit('/').next().unwrap_or(&self.project)
+    }
+
+    async fn list_versions(&self, pre_release: bool) -> source::Result<Vec<String>> {
+        self.list_versions_page(pre_release, 1).await
+    }
+
+    async fn list_versions_page(
+        &self,
+        pre_release: bool,
+        page: u32,
+    ) -> source::Result<Vec<String>> {
+        Ok(
+            list_releases(&client(self.base_url.as_deref())?, &self.project, page)
+                .await?
+                .into_iter()
+                .filter(|release| pre_release || !release.upcoming_release)
+                .map(|release| release.tag_name)
+                .collect(),
+        )
+    }
+
+    async fn resolve_version(&self, version: &'_ Version) -> source::Result<String> {
+        info!(
+            "Getting release({}) for {}",
+            version.as_tag(),
+            &self.project
+        );
+        let gitlab = client(self.base_url.as_deref())?;
+        if version == &Version::Latest {
+            // releases are sorted by their release date, newest first
+            return list_releases(&gitlab, &self.project, 1)
+                .await?
+                .into_iter()
+                .find(|release| !release.upcoming_release)
+                .map(|release| release.tag_name)
+                .ok_or_else(|| SourceError::VersionNotFound {
+                    name: self.project.to_string(),
+                    version: version.clone(),
+                });
+        }
+
+        let tag = version.as_tag();
+        match get_release_by_tag(&gitlab, &self.project, &tag).await {
+            Ok(tagged_release) => Ok(tagged_release.tag_name),
+            Err(_) => Ok(
+                get_release_by_tag(&gitlab, &self.project, &format!("v{tag}"))
+                    .await?
+                    .tag_name,
+            ),
+        }
+    }
+
+    async fn list_artifacts(&self, tag: &'_ str) -> source::Result<Vec<Artifact>> {
+        let gitlab = client(self.base_url.as_deref())?;
+        Ok(get_release_by_tag(&gitlab, &self.project, tag)
+            .await?
+            .into_artifacts())
+    }
+
+    fn download_auth(&self) -> Option<DownloadAuth> {
+        client(self.base_url.as_deref()).ok()?.download_auth()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    #[test_case("https://gitlab.example.com", Some("secret") ; "configured instance")]
+    #[test_case("https://attacker.example", None ; "foreign instance")]
+    fn client_token_tests(base_url: &'_ str, expected: Option<&'_ str>) {
+        let instance =
+            GitLab::new("https://gitlab.example.com", Some("secret".to_string())).unwrap();
+        assert_eq!(
+            client_for(&instance, Some(base_url)).unwrap().token,
+            expected.map(String::from)
+        )
+    }
+
+    #[test_case("https://gitlab.example.com", Some("secret") ; "configured instance")]
+    #[test_case("https://attacker.example", None ; "foreign instance")]
+    fn download_auth_tests(base_url: &'_ str, expected: Option<&'_ str>) {
+        let instance =
+            GitLab::new("https://gitlab.example.com", Some("secret".to_string())).unwrap();
+        let auth = client_for(&instance, Some(base_url))
+            .unwrap()
+            .download_auth();
+        assert_eq!(auth.as_ref().map(|auth| auth.value.as_str()), expected);
+        if let Some(auth) = auth {
+            assert_eq!(auth.header, "PRIVATE-TOKEN");
+            assert!(auth.applies_to(&reqwest::Url::parse(base_url).unwrap()));
+        }
+    }
+
+    #[test_case(None, "https://gitlab.example.com/api/v4/projects/group%2Fsubgroup%2Fproject/releases?per_page=100" ; "list")]
+    #[test_case(Some("v1.2.3"), "https://gitlab.example.com/api/v4/projects/group%2Fsubgroup%2Fproject/releases/v1.2.3" ; "tag")]
+    #[test_case(Some("release/1.2"), "https://gitlab.example.com/api/v4/projects/group%2Fsubgroup%2Fproject/releases/release%2F1.2" ; "tag with slash")]
+    fn releases_url_tests(tag: Option<&'_ str>, expected: &'_ str) {
+        let gitlab = GitLab::new("https://gitlab.example.com/", None).unwrap();
+        assert_eq!(
+            gitlab
+                .releases_url("group/subgroup/project", tag)
+                .unwrap()
+                .as_str(),
+            expected
+        )
+    }
+
+    #[test]
+    fn gitlab_url_with_path() {
+        let gitlab = GitLab::new("https://example.com/gitlab", None).unwrap();
+        assert_eq!(
+            gitlab.releases_url("group/project", None).unwrap().as_str(),
+            "https://example.com/gitlab/api/v4/projects/group%2Fproject/releases?per_page=100"
+        )
+    }
+
+    #[test]
+    fn release_from_api_response() {
+        let release = serde_json::from_str::<GitLabRelease>(
+            r#"{
+                "tag_name": "v1.22.0",
+                "upcoming_release": false,
+                "assets": {
+                    "count": 2,
+                    "sources": [{ "format": "zip", "url": "https://gitlab.com/gitlab-org/cli/-/archive/v1.22.0/cli-v1.22.0.zip" }],
+                    "links": [
+                        {
+                            "id": 1,
+                            "name": "glab_1.22.0_Linux_x86_64.tar.gz",
+                            "url": "https://gitlab.com/gitlab-org/cli/-/releases/v1.22.0/downloads/glab_1.22.0_Linux_x86_64.tar.gz",
+                            "direct_asset_url": "https://gitlab.com/api/v4/projects/34675721/packages/generic/glab/1.22.0/glab_1.22.0_Linux_x86_64.tar.gz",
+                            "link_type": "package"
+                        },
+                        { "id": 2, "name": "checksums.txt", "url": "https://example.com/checksums.txt" }
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(release.tag_name, "v1.22.0");
+        assert!(!release.upcoming_release);
+        assert_eq!(
+            release
+                .into_artifacts()
+                .iter()
+                .map(|a| (a.name.as_str(), a.url.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "glab_1.22.0_Linux_x86_64.tar.gz",
+                    "https://gitlab.com/api/v4/projects/34675721/packages/generic/glab/1.22.0/glab_1.22.0_Linux_x86_64.tar.gz"
+                ),
+                ("checksums.txt", "https://example.com/checksums.txt"),
+            ]
+        );
+    }
+}
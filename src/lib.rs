@@ -1,10 +1,21 @@
 mod actions;
 mod archiver;
+mod checksum;
 pub mod cli;
 pub mod dirs;
 mod download;
 mod environment;
-mod github;
+pub mod gitea;
+pub mod github;
+pub mod gitlab;
+mod lockfile;
+mod selection;
+mod signature;
+mod source;
 mod system;
+mod template;
+#[cfg(test)]
+mod test_support;
 mod tool;
+mod toolchain;
 mod version;
@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+use thiserror::Error;
+use tracing::debug;
+
+#[derive(Debug, Error)]
+pub enum LockfileError {
+    #[error("Unable to read lock file '{file_path}'. {source:?}")]
+    FileRead {
+        file_path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to deserialize lock file: {file_path}. {msg}")]
+    Deserialization {
+        file_path: std::path::PathBuf,
+        msg: String,
+    },
+    #[error("Failed to serialize lock file. {0}")]
+    Serialization(#[from] toml::ser::Error),
+    #[error("Failed to write lock file '{file_path}'. {source}")]
+    FileWrite {
+        file_path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+}
+
+type Result<T, E = LockfileError> = std::result::Result<T, E>;
+
+/// The `vers.lock` shared by the environments of a folder, each environment has its own locks.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+struct VersLock {
+    #[serde(default, rename = "env")]
+    envs: BTreeMap<String, Lockfile>,
+}
+
+impl VersLock {
+    /// Load a lock file, a missing file results in an empty lock.
+    fn load(file_path: &'_ Path) -> Result<Self> {
+        match std::fs::read_to_string(file_path) {
+            Ok(contents) => {
+                toml::from_str(&contents).map_err(|de_err| LockfileError::Deserialization {
+                    file_path: file_path.to_path_buf(),
+                    msg: de_err.to_string(),
+                })
+            }
+            Err(read_err) if read_err.kind() == std::io::ErrorKind::NotFound => {
+                debug!("Lock file {} does not exist", file_path.display());
+                Ok(Self::default())
+            }
+            Err(read_err) => Err(LockfileError::FileRead {
+                file_path: file_path.to_path_buf(),
+                source: read_err,
+            }),
+        }
+    }
+
+    /// Replace the locks of an environment, environments without any locked tool are dropped.
+    fn set(&mut self, env_name: &'_ str, lock: &'_ Lockfile) {
+        if lock.tools.is_empty() {
+            self.envs.remove(env_name);
+        } else {
+            self.envs.insert(env_name.to_string(), lock.clone());
+        }
+    }
+}
+
+/// Exact versions and assets that were installed into an environment, used by `sync` so every
+/// machine ends up with byte for byte the same tools.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Lockfile {
+    #[serde(default, rename = "tool")]
+    pub tools: Vec<LockedTool>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LockedTool {
+    pub name: String,
+    /// Version as it is stored in the environment file
+    pub version: String,
+    /// Release tag the version was resolved to
+    pub tag: String,
+    #[serde(default, rename = "asset")]
+    pub assets: Vec<LockedAsset>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LockedAsset {
+    /// Platform the asset was selected for, in the form `os/arch`
+    pub platform: String,
+    pub name: String,
+    pub url: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl Lockfile {
+    /// Load the locks of an environment from a `vers.lock`, a missing file or environment results
+    /// in an empty lock.
+    pub fn load(file_path: &'_ Path, env_name: &'_ str) -> Result<Self> {
+        Ok(VersLock::load(file_path)?
+            .envs
+            .remove(env_name)
+            .unwrap_or_default())
+    }
+
+    /// Save the locks of an environment into a `vers.lock`, the locks of the other environments in
+    /// the file are kept.
+    pub fn save(&self, file_path: &'_ Path, env_name: &'_ str) -> Result<()> {
+        let mut vers_lock = VersLock::load(file_path)?;
+        vers_lock.set(env_name, self);
+        let contents = toml::to_string_pretty(&vers_lock)?;
+        std::fs::write(file_path, contents).map_err(|write_err| LockfileError::FileWrite {
+            file_path: file_path.to_path_buf(),
+            source: write_err,
+        })
+    }
+
+    pub fn get_tool(&self, name: &'_ str) -> Option<&LockedTool> {
+        self.tools.iter().find(|t| t.name == name)
+    }
+
+    /// Record the asset installed for a tool, locking a new version drops the assets recorded for
+    /// every other platform since they no longer match.
+    pub fn record(&mut self, name: &'_ str, version: &'_ str, tag: &'_ str, asset: LockedAsset) {
+        match self.tools.iter_mut().find(|t| t.name == name) {
+            Some(locked_tool) => {
+                if locked_tool.version != version || locked_tool.tag != tag {
+                    locked_tool.version = version.to_string();
+                    locked_tool.tag = tag.to_string();
+                    locked_tool.assets.clear();
+                }
+                locked_tool.assets.retain(|a| a.platform != asset.platform);
+                locked_tool.assets.push(asset);
+            }
+            None => self.tools.push(LockedTool {
+                name: name.to_string(),
+                version: version.to_string(),
+                tag: tag.to_string(),
+                assets: vec![asset],
+            }),
+        }
+        self.tools.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    pub fn remove(&mut self, name: &'_ str) {
+        self.tools.retain(|t| t.name != name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(platform: &'_ str) -> LockedAsset {
+        LockedAsset {
+            platform: platform.to_string(),
+            name: format!("gh_{}.tar.gz", platform.replace('/', "_")),
+            url: "https://example.com/gh.tar.gz".to_string(),
+            size: 42,
+            sha256: "abc123".to_string(),
+        }
+    }
+
+    #[test]
+    fn record_new_version_drops_other_platforms() {
+        let mut lock = Lockfile::default();
+        lock.record("cli/cli", "2.9.0", "v2.9.0", asset("linux/x86_64"));
+        lock.record("cli/cli", "2.9.0", "v2.9.0", asset("macos/arm64"));
+        assert_eq!(lock.get_tool("cli/cli").unwrap().assets.len(), 2);
+
+        lock.record("cli/cli", "2.10.1", "v2.10.1", asset("macos/arm64"));
+        let locked_tool = lock.get_tool("cli/cli").unwrap();
+        assert_eq!(locked_tool.tag, "v2.10.1");
+        assert_eq!(locked_tool.assets, vec![asset("macos/arm64")]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut lock = Lockfile::default();
+        lock.record("cli/cli", "2.10.1", "v2.10.1", asset("linux/x86_64"));
+        lock.record(
+            "BurntSushi/ripgrep",
+            "13.0.0",
+            "13.0.0",
+            asset("linux/x86_64"),
+        );
+        let contents = toml::to_string_pretty(&lock).unwrap();
+        assert_eq!(toml::from_str::<Lockfile>(&contents).unwrap(), lock);
+    }
+
+    #[test]
+    fn environments_share_the_lock_file() {
+        let mut global = Lockfile::default();
+        global.record("cli/cli", "2.10.1", "v2.10.1", asset("linux/x86_64"));
+        let mut ci = Lockfile::default();
+        ci.record(
+            "BurntSushi/ripgrep",
+            "13.0.0",
+            "13.0.0",
+            asset("linux/x86_64"),
+        );
+
+        let mut vers_lock = VersLock::default();
+        vers_lock.set("global", &global);
+        vers_lock.set("ci", &ci);
+        let contents = toml::to_string_pretty(&vers_lock).unwrap();
+        assert!(contents.contains("[[env.global.tool]]"));
+        let mut vers_lock = toml::from_str::<VersLock>(&contents).unwrap();
+        assert_eq!(vers_lock.envs["global"], global);
+        assert_eq!(vers_lock.envs["ci"], ci);
+
+        vers_lock.set("ci", &Lockfile::default());
+        assert_eq!(vers_lock.envs.keys().collect::<Vec<_>>(), vec!["global"]);
+    }
+}
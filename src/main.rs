@@ -3,7 +3,7 @@
 
 use tracing::{debug, info};
 use tracing_subscriber::{filter::filter_fn, prelude::*};
-use vers::{cli::Cli, dirs};
+use vers::{cli::Cli, dirs, gitea, github, gitlab};
 
 #[async_std::main]
 async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -23,29 +23,62 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
 
     let config_dir: std::path::PathBuf = match opts.data_dir {
         Some(dir) => dir,
-        None => dirs::get_default_config_path(),
+        None => {
+            // a project with a local environment takes priority over the global config
+            let current_dir = std::env::current_dir()?;
+            match dirs::use_local_config_path(&current_dir, opts.local)? {
+                Some(local_dir) => {
+                    info!("Using the local environment in {}", local_dir.display());
+                    local_dir
+                }
+                None => dirs::get_default_config_path(),
+            }
+        }
     };
     debug!("Config dir: {}", &config_dir.display());
 
-    // initialize Octocrab with a configured GitHub API token if available
-    if let Some(api_token) = opts.github_token {
-        info!("Initializing the GitHub client with token from CLI args");
-        octocrab::initialise(
-            octocrab::Octocrab::builder()
-                .personal_token(api_token)
-                .build()?,
-        );
-    } else if let Some(env_api_token) = std::env::var_os("GITHUB_TOKEN") {
-        info!("Initializing the GitHub client with token from environment");
-        octocrab::initialise(
-            octocrab::Octocrab::builder()
-                .personal_token(env_api_token.to_str().unwrap().into())
-                .build()?,
-        );
+    // initialize Octocrab with a configured GitHub API token and server if available
+    let github_token = match opts.github_token {
+        Some(api_token) => {
+            info!("Initializing the GitHub client with token from CLI args");
+            Some(api_token)
+        }
+        None => std::env::var("GITHUB_TOKEN").ok().map(|env_api_token| {
+            info!("Initializing the GitHub client with token from environment");
+            env_api_token
+        }),
     };
+    let github_url = opts
+        .github_url
+        .or_else(|| std::env::var("GITHUB_API_URL").ok());
+    github::initialise(github_url.as_deref(), github_token)?;
+
+    // initialize the GitLab client when a self-hosted instance or token is configured
+    let gitlab_url = opts.gitlab_url.or_else(|| std::env::var("GITLAB_URL").ok());
+    let gitlab_token = opts
+        .gitlab_token
+        .or_else(|| std::env::var("GITLAB_TOKEN").ok());
+    if gitlab_url.is_some() || gitlab_token.is_some() {
+        let gitlab_url = gitlab_url.unwrap_or_else(|| gitlab::DEFAULT_GITLAB_URL.to_string());
+        info!("Initializing the GitLab client for {}", &gitlab_url);
+        gitlab::initialise(gitlab::GitLab::new(&gitlab_url, gitlab_token)?);
+    }
+
+    // same for Gitea and Forgejo
+    let gitea_url = opts.gitea_url.or_else(|| std::env::var("GITEA_URL").ok());
+    let gitea_token = opts
+        .gitea_token
+        .or_else(|| std::env::var("GITEA_TOKEN").ok());
+    if gitea_url.is_some() || gitea_token.is_some() {
+        let gitea_url = gitea_url.unwrap_or_else(|| gitea::DEFAULT_GITEA_URL.to_string());
+        info!("Initializing the Gitea client for {}", &gitea_url);
+        gitea::initialise(gitea::Gitea::new(&gitea_url, gitea_token)?);
+    }
 
     // Run the main logic
-    opts.action.execute(config_dir, &opts.env).await?;
+    opts.action
+        .execute(config_dir, &opts.env, &opts.install)
+        .await?;
 
     Ok(())
 }
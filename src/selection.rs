@@ -0,0 +1,392 @@
+use crate::{
+    archiver::ArchiveFormat,
+    source::Artifact,
+    system::{Libc, OperatingSystem, System},
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use skim::prelude::*;
+use std::{
+    fmt::Display,
+    io::{Cursor, IsTerminal},
+    path::Path,
+};
+use tracing::{debug, info};
+
+/// Files published next to the assets of a release that are never installed, with the reason
+/// shown by `vers add --explain`.
+static METADATA_REGEXES: Lazy<Vec<(Regex, &'static str)>> = Lazy::new(|| {
+    [
+        (
+            r#"(?i)(\.(sha\d+|md5)(sum)?|(^|[._-])(checksums?|sha\d+sums?)(\.txt)?)$"#,
+            "is a checksum file",
+        ),
+        (
+            r#"(?i)\.(sig|asc|minisig|pem|crt|cert|sigstore|sigstore\.json|bundle)$"#,
+            "is a signature or certificate",
+        ),
+        (
+            r#"(?i)(sbom|\.spdx(\.json)?$|\.cdx(\.json|\.xml)?$|\.intoto\.jsonl$|\.att$)"#,
+            "is a software bill of materials or an attestation",
+        ),
+        (
+            r#"(?i)([._-](debug|dbg|dbgsym)([._-]|$)|\.pdb$|\.dsym)"#,
+            "contains debug symbols",
+        ),
+        (
+            r#"(?i)\.(zsync|txt|json|ya?ml|md|html|pub)$"#,
+            "is metadata of the release",
+        ),
+    ]
+    .into_iter()
+    .map(|(regex, reason)| {
+        (
+            Regex::new(regex).expect("unable to create regex for release metadata"),
+            reason,
+        )
+    })
+    .collect()
+});
+
+/// Points metadata files lose, they are never picked whatever else they match.
+const METADATA_PENALTY: i32 = -100;
+
+/// Why an asset gained or lost points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reason {
+    pub points: i32,
+    pub description: String,
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:+} {}", self.points, self.description)
+    }
+}
+
+/// The score of a release asset for a platform, the eligible asset with the most points is
+/// installed.
+#[derive(Debug, Clone)]
+pub struct AssetScore {
+    pub artifact: Artifact,
+    pub points: i32,
+    /// Assets for other platforms, that don't match the asset pattern or that are metadata of the
+    /// release are never installed.
+    pub eligible: bool,
+    pub reasons: Vec<Reason>,
+}
+
+impl AssetScore {
+    fn new(artifact: &'_ Artifact) -> Self {
+        Self {
+            artifact: artifact.clone(),
+            points: 0,
+            eligible: true,
+            reasons: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, points: i32, description: impl Into<String>) {
+        self.points += points;
+        self.reasons.push(Reason {
+            points,
+            description: description.into(),
+        });
+    }
+
+    fn exclude(&mut self, description: impl Into<String>) {
+        self.eligible = false;
+        self.add(0, description);
+    }
+}
+
+/// Score every asset of a release for a platform, best first. An asset pattern replaces the
+/// platform detection, only assets matching it are eligible.
+pub fn score_assets(
+    artifacts: &'_ [Artifact],
+    system: &'_ System,
+    user_pattern: &'_ str,
+) -> Vec<AssetScore> {
+    let pattern = match user_pattern {
+        "" => None,
+        pattern => Some(Regex::new(pattern)),
+    };
+    let mut scores: Vec<AssetScore> = artifacts
+        .iter()
+        .map(|artifact| {
+            let name = artifact.name.as_str();
+            let mut score = AssetScore::new(artifact);
+            match &pattern {
+                Some(Ok(pattern)) if pattern.is_match(name) => {
+                    score.add(0, format!("matches the asset pattern '{pattern}'"))
+                }
+                Some(Ok(pattern)) => {
+                    score.exclude(format!("doesn't match the asset pattern '{pattern}'"))
+                }
+                Some(Err(_)) => score.exclude("the asset pattern isn't a valid regular expression"),
+                None if system.is_match(name) || system.is_app_image_match(name) => (),
+                None => score.exclude(format!("isn't built for {}", system.platform())),
+            }
+            score_platform(&mut score, system);
+            score_format(&mut score, system);
+            for (regex, reason) in METADATA_REGEXES.iter() {
+                if regex.is_match(name) {
+                    score.add(METADATA_PENALTY, *reason);
+                    score.eligible = false;
+                    break;
+                }
+            }
+            debug!("Asset '{name}' scored {}", score.points);
+            score
+        })
+        .collect();
+    scores.sort_by(|a, b| {
+        (b.eligible, b.points)
+            .cmp(&(a.eligible, a.points))
+            .then_with(|| a.artifact.name.cmp(&b.artifact.name))
+    });
+    scores
+}
+
+fn score_platform(score: &'_ mut AssetScore, system: &'_ System) {
+    let name = score.artifact.name.to_string();
+    if system.matches_os(&name) {
+        score.add(10, format!("names the operating system {}", system.os));
+    }
+    if system.architecture.is_match(&name) {
+        score.add(
+            10,
+            format!("names the architecture {}", system.architecture),
+        );
+    } else if system.matches_universal(&name) {
+        // a binary for the architecture alone is smaller
+        score.add(8, "is a universal binary");
+    }
+    if system.os == OperatingSystem::Linux {
+        let lower_name = name.to_lowercase();
+        let musl = lower_name.contains("musl");
+        let is_static = !musl && lower_name.contains("static");
+        let gnu = !musl && (lower_name.contains("gnu") || lower_name.contains("glibc"));
+        match system.libc {
+            Some(Libc::Musl) if musl => score.add(3, "is linked against musl"),
+            Some(Libc::Musl) if is_static => score.add(3, "is linked statically"),
+            // the asset is still used when it is the only one, the tool may be static anyway
+            Some(Libc::Musl) if gnu => score.add(-10, "is linked against glibc, which is missing"),
+            Some(Libc::Gnu) | None if gnu => score.add(2, "is linked against glibc"),
+            Some(Libc::Gnu) | None if musl => score.add(1, "is linked statically against musl"),
+            Some(Libc::Gnu) | None if is_static => score.add(1, "is linked statically"),
+            _ => (),
+        }
+    }
+}
+
+fn score_format(score: &'_ mut AssetScore, system: &'_ System) {
+    let name = score.artifact.name.to_string();
+    match ArchiveFormat::from_extension(Path::new(&name)) {
+        Some(ArchiveFormat::Tar | ArchiveFormat::CompressedTar(_)) => {
+            score.add(3, "is a tar archive")
+        }
+        Some(ArchiveFormat::Zip) if system.os == OperatingSystem::Windows => {
+            score.add(3, "is a zip archive")
+        }
+        Some(ArchiveFormat::Zip) => score.add(2, "is a zip archive"),
+        Some(ArchiveFormat::Compressed(_)) => score.add(2, "is a compressed executable"),
+        Some(ArchiveFormat::Deb | ArchiveFormat::Rpm) => score.add(1, "is a Linux package"),
+        Some(ArchiveFormat::AppImage) => score.add(-5, "is an AppImage, it needs FUSE to run"),
+        None if !has_extension(&name) => score.add(2, "is an executable"),
+        None => (),
+    }
+}
+
+/// Names like `tool_1.2.0_linux_amd64` have dots without having an extension.
+fn has_extension(name: &'_ str) -> bool {
+    name.rsplit_once('.').map_or(false, |(_, extension)| {
+        !extension.is_empty()
+            && extension.len() <= 8
+            && extension.chars().all(|c| c.is_ascii_alphabetic())
+    })
+}
+
+/// The eligible assets with the highest score, more than one when they tie.
+pub fn best_assets(scores: &'_ [AssetScore]) -> Vec<&'_ AssetScore> {
+    let best = scores
+        .iter()
+        .filter(|score| score.eligible)
+        .map(|score| score.points)
+        .max();
+    scores
+        .iter()
+        .filter(|score| score.eligible && Some(score.points) == best)
+        .collect()
+}
+
+/// Pick the asset to install. Ties are only resolved by asking when running in a terminal,
+/// otherwise the first asset by name is picked so the result doesn't change between runs.
+pub fn pick_asset(scores: &'_ [AssetScore]) -> Option<Artifact> {
+    let best = best_assets(scores);
+    match best.len() {
+        0 => None,
+        1 => Some(best[0].artifact.clone()),
+        _ if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() => {
+            prompt_asset(&best)
+        }
+        _ => {
+            info!(
+                "Assets {} score the same, picking the first one",
+                best.iter()
+                    .map(|score| score.artifact.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            Some(best[0].artifact.clone())
+        }
+    }
+}
+
+/// Let the user choose between assets that tie, nothing is picked when the prompt is aborted.
+fn prompt_asset(best: &'_ [&'_ AssetScore]) -> Option<Artifact> {
+    let item_reader = SkimItemReader::default().of_bufread(Cursor::new(
+        best.iter()
+            .map(|score| score.artifact.name.to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    ));
+    let options = SkimOptionsBuilder::default()
+        .height(Some("75%"))
+        .build()
+        .expect("skim options are valid");
+    let output = Skim::run_with(&options, Some(item_reader))?;
+    if output.is_abort {
+        return None;
+    }
+    let selected = output.selected_items.first()?.text().to_string();
+    best.iter()
+        .find(|score| score.artifact.name == selected)
+        .map(|score| score.artifact.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::system::{OperatingSystem, PlatformArchitecture},
+        test_case::test_case,
+    };
+
+    fn artifacts(names: &'_ [&'_ str]) -> Vec<Artifact> {
+        names
+            .iter()
+            .map(|name| Artifact {
+                name: name.to_string(),
+                url: reqwest::Url::parse(&format!("https://example.com/{name}")).unwrap(),
+            })
+            .collect()
+    }
+
+    fn linux(architecture: PlatformArchitecture) -> System {
+        System {
+            architecture,
+            os: OperatingSystem::Linux,
+            libc: Some(Libc::Gnu),
+        }
+    }
+
+    #[test_case(&[
+        "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz",
+        "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz.sha256",
+        "ripgrep-14.1.0-aarch64-unknown-linux-gnu.tar.gz",
+        "ripgrep-14.1.0-x86_64-pc-windows-msvc.zip",
+    ], "", Some("ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz") ; "checksum penalised")]
+    #[test_case(&[
+        "tool_linux_amd64.tar.gz.sig",
+        "tool_linux_amd64.sbom.json",
+        "tool_linux_amd64_debug.tar.gz",
+        "tool_linux_amd64.zip",
+    ], "", Some("tool_linux_amd64.zip") ; "metadata penalised")]
+    #[test_case(&[
+        "tool-linux-amd64",
+        "tool-linux-amd64.tar.gz",
+    ], "", Some("tool-linux-amd64.tar.gz") ; "archive preferred")]
+    #[test_case(&[
+        "nvim-linux-x86_64.tar.gz",
+        "nvim-linux-x86_64.appimage",
+        "nvim-linux-x86_64.appimage.zsync",
+    ], "", Some("nvim-linux-x86_64.tar.gz") ; "appimage last")]
+    #[test_case(&["nvim.appimage", "nvim.appimage.zsync", "nvim-macos.tar.gz"], "", Some("nvim.appimage") ; "appimage without platform")]
+    #[test_case(&["tool_1.2.0_linux_amd64.sha256"], "", None ; "only metadata")]
+    #[test_case(&["tool-darwin.tar.gz", "tool-static.tar.gz"], "static", Some("tool-static.tar.gz") ; "asset pattern")]
+    #[test_case(&["tool-linux-amd64.tar.gz"], "(", None ; "invalid asset pattern")]
+    fn pick_asset_tests(names: &'_ [&'_ str], user_pattern: &'_ str, expected: Option<&'_ str>) {
+        let scores = score_assets(
+            &artifacts(names),
+            &linux(PlatformArchitecture::Amd64),
+            user_pattern,
+        );
+        assert_eq!(
+            pick_asset(&scores).map(|artifact| artifact.name),
+            expected.map(String::from)
+        )
+    }
+
+    #[test]
+    fn ties_are_resolved_by_name() {
+        let names = ["tool-linux-x86_64.tar.xz", "tool-linux-amd64.tar.gz"];
+        let scores = score_assets(&artifacts(&names), &linux(PlatformArchitecture::Amd64), "");
+        assert_eq!(best_assets(&scores).len(), 2);
+        assert_eq!(
+            scores[0].artifact.name, "tool-linux-amd64.tar.gz",
+            "tied assets are sorted by name"
+        );
+    }
+
+    #[test]
+    fn reasons_explain_the_score() {
+        let scores = score_assets(
+            &artifacts(&["tool-x86_64-unknown-linux-musl.tar.gz"]),
+            &linux(PlatformArchitecture::Amd64),
+            "",
+        );
+        assert_eq!(
+            scores[0]
+                .reasons
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "+10 names the operating system linux",
+                "+10 names the architecture x86_64",
+                "+1 is linked statically against musl",
+                "+3 is a tar archive",
+            ]
+        );
+        assert_eq!(scores[0].points, 24);
+    }
+
+    const RIPGREP_LINUX: [&str; 2] = [
+        "ripgrep-14.1.0-x86_64-unknown-linux-gnu.tar.gz",
+        "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz",
+    ];
+
+    #[test_case(&RIPGREP_LINUX, Libc::Gnu, "ripgrep-14.1.0-x86_64-unknown-linux-gnu.tar.gz" ; "glibc")]
+    #[test_case(&RIPGREP_LINUX, Libc::Musl, "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz" ; "musl")]
+    #[test_case(&["tool-linux-amd64-gnu.tar.gz", "tool-linux-amd64-static.tar.gz"], Libc::Musl, "tool-linux-amd64-static.tar.gz" ; "static on musl")]
+    #[test_case(&["tool-linux-amd64-gnu.tar.gz"], Libc::Musl, "tool-linux-amd64-gnu.tar.gz" ; "only glibc on musl")]
+    fn libc_preference_tests(names: &'_ [&'_ str], libc: Libc, expected: &'_ str) {
+        let system = System {
+            libc: Some(libc),
+            ..linux(PlatformArchitecture::Amd64)
+        };
+        let scores = score_assets(&artifacts(names), &system, "");
+        assert_eq!(
+            pick_asset(&scores).map(|artifact| artifact.name),
+            Some(expected.to_string())
+        )
+    }
+
+    #[test_case("tool_1.2.0_linux_amd64", false ; "version dots")]
+    #[test_case("tool.exe", true ; "extension")]
+    #[test_case("tool-linux", false ; "no dots")]
+    fn has_extension_tests(name: &'_ str, expected: bool) {
+        assert_eq!(has_extension(name), expected)
+    }
+}
@@ -0,0 +1,622 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use thiserror::Error;
+use tracing::{debug, info};
+use x509_cert::{
+    der::{
+        asn1::{ObjectIdentifier, Utf8StringRef},
+        Decode, DecodePem, Encode,
+    },
+    ext::pkix::{name::GeneralName, SubjectAltName},
+    Certificate,
+};
+
+const SUBJECT_ALT_NAME_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.17");
+const FULCIO_ISSUER_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.57264.1.1");
+const FULCIO_ISSUER_V2_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.57264.1.8");
+const ECDSA_WITH_SHA256_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const ECDSA_WITH_SHA384_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+
+#[derive(Debug, Error)]
+pub enum SignatureError {
+    #[error("No {kind} signature was found in the release for '{asset_name}'")]
+    NotFound {
+        kind: SignatureKind,
+        asset_name: String,
+    },
+    #[error("Invalid {kind} key configured. {message}")]
+    InvalidKey {
+        kind: SignatureKind,
+        message: String,
+    },
+    #[error("Invalid {kind} signature '{file_path}'. {message}")]
+    InvalidSignature {
+        kind: SignatureKind,
+        file_path: PathBuf,
+        message: String,
+    },
+    #[error("{kind} signature of '{file_path}' could not be verified. {message}")]
+    VerificationFailed {
+        kind: SignatureKind,
+        file_path: PathBuf,
+        message: String,
+    },
+    #[error("Unable to read '{file_path}'. {source}")]
+    Io {
+        file_path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+type Result<T, E = SignatureError> = std::result::Result<T, E>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureKind {
+    Minisign,
+    Cosign,
+    Gpg,
+}
+
+impl Display for SignatureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            SignatureKind::Minisign => write!(f, "minisign"),
+            SignatureKind::Cosign => write!(f, "cosign"),
+            SignatureKind::Gpg => write!(f, "GPG"),
+        }
+    }
+}
+
+impl SignatureKind {
+    /// Extensions added to the signed file name for the detached signature, most common first.
+    fn signature_extensions(&self) -> &'static [&'static str] {
+        match self {
+            SignatureKind::Minisign => &["minisig"],
+            SignatureKind::Cosign => &["sig"],
+            SignatureKind::Gpg => &["asc", "sig", "gpg"],
+        }
+    }
+}
+
+/// Keys used to verify the signatures shipped with a tool's releases.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", default)]
+pub struct Verification {
+    /// minisign public key, the base64 encoded line of a `minisign.pub` file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minisign: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cosign: Option<CosignVerification>,
+    /// ASCII armored GPG public key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpg: Option<String>,
+}
+
+/// cosign signatures are verified with either a public key or, for keyless signing, a certificate
+/// that has to chain up to one of the trusted roots and match the expected identity.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", default)]
+pub struct CosignVerification {
+    /// PEM encoded public key used with `cosign sign-blob --key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Expected identity (email or URI) in the certificate of a keyless signature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate_identity: Option<String>,
+    /// Expected OIDC issuer in the certificate of a keyless signature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate_oidc_issuer: Option<String>,
+    /// PEM bundle of the certificates (roots and intermediates) keyless certificates chain to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trusted_roots: Option<String>,
+}
+
+impl Verification {
+    pub fn is_empty(&self) -> bool {
+        self.kinds().is_empty()
+    }
+
+    /// The kinds of signatures that have a key configured, every one of them has to pass.
+    pub fn kinds(&self) -> Vec<SignatureKind> {
+        let mut kinds = Vec::new();
+        if self.minisign.is_some() {
+            kinds.push(SignatureKind::Minisign);
+        }
+        if self.cosign.is_some() {
+            kinds.push(SignatureKind::Cosign);
+        }
+        if self.gpg.is_some() {
+            kinds.push(SignatureKind::Gpg);
+        }
+        kinds
+    }
+
+    /// Verify `signature_path` is a valid signature of `file_path`.
+    ///
+    /// `certificate_path` is only used by cosign keyless signatures. Everything is verified
+    /// offline using the downloaded files and the configured keys.
+    pub fn verify(
+        &self,
+        kind: SignatureKind,
+        file_path: &'_ Path,
+        signature_path: &'_ Path,
+        certificate_path: Option<&'_ Path>,
+    ) -> Result<()> {
+        debug!(
+            "Verifying {} signature {} of {}",
+            kind,
+            signature_path.display(),
+            file_path.display()
+        );
+        match kind {
+            SignatureKind::Minisign => {
+                verify_minisign(self.minisign.as_deref(), file_path, signature_path)
+            }
+            SignatureKind::Cosign => verify_cosign(
+                self.cosign.as_ref(),
+                file_path,
+                signature_path,
+                certificate_path,
+            ),
+            SignatureKind::Gpg => verify_gpg(self.gpg.as_deref(), file_path, signature_path),
+        }?;
+        info!("Verified {} signature of {}", kind, file_path.display());
+        Ok(())
+    }
+}
+
+/// Find the detached signature of `signed_name` in a release.
+pub fn find_signature_asset_name<'a>(
+    kind: SignatureKind,
+    signed_name: &'_ str,
+    release_asset_names: &'_ [&'a str],
+) -> Option<&'a str> {
+    kind.signature_extensions().iter().find_map(|ext| {
+        let signature_name = format!("{signed_name}.{ext}");
+        release_asset_names
+            .iter()
+            .find(|name| **name == signature_name)
+            .copied()
+    })
+}
+
+/// Find the certificate uploaded next to a cosign keyless signature.
+pub fn find_certificate_asset_name<'a>(
+    signed_name: &'_ str,
+    release_asset_names: &'_ [&'a str],
+) -> Option<&'a str> {
+    ["pem", "cert", "crt"].iter().find_map(|ext| {
+        let certificate_name = format!("{signed_name}.{ext}");
+        release_asset_names
+            .iter()
+            .find(|name| **name == certificate_name)
+            .copied()
+    })
+}
+
+fn read_file(file_path: &'_ Path) -> Result<Vec<u8>> {
+    std::fs::read(file_path).map_err(|source| SignatureError::Io {
+        file_path: file_path.to_path_buf(),
+        source,
+    })
+}
+
+fn missing_key(kind: SignatureKind) -> SignatureError {
+    SignatureError::InvalidKey {
+        kind,
+        message: "no key is configured".to_string(),
+    }
+}
+
+fn verify_minisign(
+    public_key: Option<&'_ str>,
+    file_path: &'_ Path,
+    signature_path: &'_ Path,
+) -> Result<()> {
+    let kind = SignatureKind::Minisign;
+    let public_key = public_key.ok_or_else(|| missing_key(kind))?;
+    // accept the contents of a minisign.pub file as well as just the key
+    let public_key = public_key
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("untrusted comment:"))
+        .last()
+        .unwrap_or_default();
+    let public_key = minisign_verify::PublicKey::from_base64(public_key).map_err(|key_err| {
+        SignatureError::InvalidKey {
+            kind,
+            message: key_err.to_string(),
+        }
+    })?;
+
+    let signature = String::from_utf8_lossy(&read_file(signature_path)?).to_string();
+    let signature = minisign_verify::Signature::decode(&signature).map_err(|sig_err| {
+        SignatureError::InvalidSignature {
+            kind,
+            file_path: signature_path.to_path_buf(),
+            message: sig_err.to_string(),
+        }
+    })?;
+
+    public_key
+        .verify(&read_file(file_path)?, &signature, false)
+        .map_err(|verify_err| SignatureError::VerificationFailed {
+            kind,
+            file_path: file_path.to_path_buf(),
+            message: verify_err.to_string(),
+        })
+}
+
+/// cosign uploads signatures and certificates base64 encoded, decode them when needed.
+fn decode_cosign_file(contents: &'_ [u8]) -> Vec<u8> {
+    let trimmed: Vec<u8> = contents
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    STANDARD
+        .decode(trimmed)
+        .unwrap_or_else(|_| contents.to_vec())
+}
+
+fn verify_cosign(
+    config: Option<&'_ CosignVerification>,
+    file_path: &'_ Path,
+    signature_path: &'_ Path,
+    certificate_path: Option<&'_ Path>,
+) -> Result<()> {
+    let kind = SignatureKind::Cosign;
+    let config = config.ok_or_else(|| missing_key(kind))?;
+    let signature =
+        p256::ecdsa::Signature::from_der(&decode_cosign_file(&read_file(signature_path)?))
+            .map_err(|sig_err| SignatureError::InvalidSignature {
+                kind,
+                file_path: signature_path.to_path_buf(),
+                message: sig_err.to_string(),
+            })?;
+
+    let verifying_key = match (&config.key, certificate_path) {
+        (Some(key), _) => {
+            use p256::pkcs8::DecodePublicKey;
+            p256::ecdsa::VerifyingKey::from_public_key_pem(key.trim()).map_err(|key_err| {
+                SignatureError::InvalidKey {
+                    kind,
+                    message: key_err.to_string(),
+                }
+            })?
+        }
+        (None, Some(certificate_path)) => {
+            let certificate = Certificate::from_pem(decode_cosign_file(&read_file(
+                certificate_path,
+            )?))
+            .map_err(|cert_err| SignatureError::InvalidSignature {
+                kind,
+                file_path: certificate_path.to_path_buf(),
+                message: cert_err.to_string(),
+            })?;
+            verify_keyless_certificate(config, &certificate).map_err(|message| {
+                SignatureError::VerificationFailed {
+                    kind,
+                    file_path: certificate_path.to_path_buf(),
+                    message,
+                }
+            })?;
+            p256_key_from_certificate(&certificate).map_err(|message| {
+                SignatureError::InvalidSignature {
+                    kind,
+                    file_path: certificate_path.to_path_buf(),
+                    message,
+                }
+            })?
+        }
+        (None, None) => {
+            return Err(SignatureError::NotFound {
+                kind,
+                asset_name: format!("{} certificate", signature_path.display()),
+            })
+        }
+    };
+
+    use p256::ecdsa::signature::Verifier;
+    verifying_key
+        .verify(&read_file(file_path)?, &signature)
+        .map_err(|verify_err| SignatureError::VerificationFailed {
+            kind,
+            file_path: file_path.to_path_buf(),
+            message: verify_err.to_string(),
+        })
+}
+
+fn p256_key_from_certificate(
+    certificate: &'_ Certificate,
+) -> std::result::Result<p256::ecdsa::VerifyingKey, String> {
+    use p256::pkcs8::DecodePublicKey;
+    let spki = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|der_err| der_err.to_string())?;
+    p256::ecdsa::VerifyingKey::from_public_key_der(&spki).map_err(|key_err| key_err.to_string())
+}
+
+fn p384_key_from_certificate(
+    certificate: &'_ Certificate,
+) -> std::result::Result<p384::ecdsa::VerifyingKey, String> {
+    use p384::pkcs8::DecodePublicKey;
+    let spki = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|der_err| der_err.to_string())?;
+    p384::ecdsa::VerifyingKey::from_public_key_der(&spki).map_err(|key_err| key_err.to_string())
+}
+
+/// Check `certificate` was issued by `issuer`, only the ECDSA algorithms used by Fulcio are
+/// supported.
+fn is_signed_by(certificate: &'_ Certificate, issuer: &'_ Certificate) -> bool {
+    use p256::ecdsa::signature::Verifier;
+
+    if certificate.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+        return false;
+    }
+    let tbs = match certificate.tbs_certificate.to_der() {
+        Ok(tbs) => tbs,
+        Err(_) => return false,
+    };
+    let signature = certificate.signature.raw_bytes();
+    let algorithm = certificate.signature_algorithm.oid;
+
+    if algorithm == ECDSA_WITH_SHA384_OID {
+        match (
+            p384_key_from_certificate(issuer),
+            p384::ecdsa::Signature::from_der(signature),
+        ) {
+            (Ok(key), Ok(signature)) => key.verify(&tbs, &signature).is_ok(),
+            _ => false,
+        }
+    } else if algorithm == ECDSA_WITH_SHA256_OID {
+        match (
+            p256_key_from_certificate(issuer),
+            p256::ecdsa::Signature::from_der(signature),
+        ) {
+            (Ok(key), Ok(signature)) => key.verify(&tbs, &signature).is_ok(),
+            _ => false,
+        }
+    } else {
+        false
+    }
+}
+
+fn certificate_extension<'a>(
+    certificate: &'a Certificate,
+    oid: &'_ ObjectIdentifier,
+) -> Option<&'a [u8]> {
+    certificate
+        .tbs_certificate
+        .extensions
+        .as_ref()?
+        .iter()
+        .find(|ext| ext.extn_id == *oid)
+        .map(|ext| ext.extn_value.as_bytes())
+}
+
+fn certificate_identities(certificate: &'_ Certificate) -> Vec<String> {
+    certificate_extension(certificate, &SUBJECT_ALT_NAME_OID)
+        .and_then(|value| SubjectAltName::from_der(value).ok())
+        .map(|san| {
+            san.0
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::Rfc822Name(email) => Some(email.to_string()),
+                    GeneralName::UniformResourceIdentifier(uri) => Some(uri.to_string()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn certificate_oidc_issuer(certificate: &'_ Certificate) -> Option<String> {
+    if let Some(value) = certificate_extension(certificate, &FULCIO_ISSUER_V2_OID) {
+        if let Ok(issuer) = Utf8StringRef::from_der(value) {
+            return Some(issuer.to_string());
+        }
+    }
+    certificate_extension(certificate, &FULCIO_ISSUER_OID)
+        .map(|value| String::from_utf8_lossy(value).to_string())
+}
+
+/// Validate a keyless signing certificate chains up to a trusted root and was issued for the
+/// expected identity.
+///
+/// Keyless certificates are only valid for a couple of minutes around the signing time, without a
+/// transparency log entry there is no trusted signing time so the validity period isn't checked.
+fn verify_keyless_certificate(
+    config: &'_ CosignVerification,
+    certificate: &'_ Certificate,
+) -> std::result::Result<(), String> {
+    let (expected_identity, expected_issuer, trusted_roots) = match (
+        &config.certificate_identity,
+        &config.certificate_oidc_issuer,
+        &config.trusted_roots,
+    ) {
+        (Some(identity), Some(issuer), Some(roots)) => (identity, issuer, roots),
+        _ => {
+            return Err("keyless verification requires `certificate_identity`, `certificate_oidc_issuer` and `trusted_roots` to be configured".to_string())
+        }
+    };
+
+    let identities = certificate_identities(certificate);
+    if !identities.iter().any(|i| i == expected_identity) {
+        return Err(format!(
+            "certificate identities {identities:?} do not include '{expected_identity}'"
+        ));
+    }
+    match certificate_oidc_issuer(certificate) {
+        Some(issuer) if &issuer == expected_issuer => {}
+        issuer => {
+            return Err(format!(
+                "certificate OIDC issuer {issuer:?} is not '{expected_issuer}'"
+            ))
+        }
+    }
+
+    let trusted = Certificate::load_pem_chain(trusted_roots.as_bytes())
+        .map_err(|pem_err| format!("unable to load the trusted roots. {pem_err}"))?;
+    let mut current = certificate;
+    // bound the walk so a misconfigured bundle with cycles can't loop forever
+    for _ in 0..=trusted.len() {
+        let issuer = match trusted.iter().find(|t| is_signed_by(current, t)) {
+            Some(issuer) => issuer,
+            None => break,
+        };
+        if issuer.tbs_certificate.issuer == issuer.tbs_certificate.subject {
+            return Ok(());
+        }
+        current = issuer;
+    }
+    Err("certificate does not chain up to a trusted root".to_string())
+}
+
+fn verify_gpg(
+    public_key: Option<&'_ str>,
+    file_path: &'_ Path,
+    signature_path: &'_ Path,
+) -> Result<()> {
+    let kind = SignatureKind::Gpg;
+    let public_key = public_key.ok_or_else(|| missing_key(kind))?;
+    // use a throw away keyring so only the configured key is trusted
+    let home_dir = std::env::temp_dir().join(format!("vers-gpg-{}", std::process::id()));
+    let to_io_err = |source| SignatureError::Io {
+        file_path: home_dir.clone(),
+        source,
+    };
+    std::fs::create_dir_all(&home_dir).map_err(to_io_err)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&home_dir, std::fs::Permissions::from_mode(0o700))
+            .map_err(to_io_err)?;
+    }
+
+    let result = (|| {
+        let key_file = home_dir.join("key.asc");
+        std::fs::write(&key_file, public_key).map_err(to_io_err)?;
+        let run_gpg = |args: &[&std::ffi::OsStr]| {
+            Command::new("gpg")
+                .arg("--batch")
+                .arg("--homedir")
+                .arg(&home_dir)
+                .args(args)
+                .output()
+                .map_err(to_io_err)
+        };
+
+        let import = run_gpg(&["--import".as_ref(), key_file.as_os_str()])?;
+        if !import.status.success() {
+            return Err(SignatureError::InvalidKey {
+                kind,
+                message: String::from_utf8_lossy(&import.stderr).trim().to_string(),
+            });
+        }
+        let verify = run_gpg(&[
+            "--verify".as_ref(),
+            signature_path.as_os_str(),
+            file_path.as_os_str(),
+        ])?;
+        if !verify.status.success() {
+            return Err(SignatureError::VerificationFailed {
+                kind,
+                file_path: file_path.to_path_buf(),
+                message: String::from_utf8_lossy(&verify.stderr).trim().to_string(),
+            });
+        }
+        Ok(())
+    })();
+
+    let _ = std::fs::remove_dir_all(&home_dir);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::test_support::TempDir, test_case::test_case};
+
+    const RELEASE: &[&str] = &[
+        "tool_linux_amd64.tar.gz",
+        "tool_linux_amd64.tar.gz.minisig",
+        "tool_linux_amd64.tar.gz.asc",
+        "checksums.txt",
+        "checksums.txt.sig",
+        "checksums.txt.pem",
+    ];
+
+    #[test_case(SignatureKind::Minisign, "tool_linux_amd64.tar.gz", Some("tool_linux_amd64.tar.gz.minisig") ; "minisign")]
+    #[test_case(SignatureKind::Gpg, "tool_linux_amd64.tar.gz", Some("tool_linux_amd64.tar.gz.asc") ; "gpg armored")]
+    #[test_case(SignatureKind::Cosign, "tool_linux_amd64.tar.gz", None ; "cosign missing")]
+    #[test_case(SignatureKind::Cosign, "checksums.txt", Some("checksums.txt.sig") ; "cosign checksums")]
+    fn find_signature_asset_name_tests(
+        kind: SignatureKind,
+        signed_name: &'_ str,
+        expected: Option<&'_ str>,
+    ) {
+        assert_eq!(
+            find_signature_asset_name(kind, signed_name, RELEASE),
+            expected
+        )
+    }
+
+    #[test]
+    fn find_certificate() {
+        assert_eq!(
+            find_certificate_asset_name("checksums.txt", RELEASE),
+            Some("checksums.txt.pem")
+        )
+    }
+
+    #[test]
+    fn verify_cosign_key() {
+        use p256::{
+            ecdsa::{signature::Signer, Signature, SigningKey},
+            pkcs8::{EncodePublicKey, LineEnding},
+        };
+
+        let temp_dir = TempDir::new("cosign-key");
+        let dir = temp_dir.path();
+        let signing_key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let file_path = dir.join("tool.tar.gz");
+        let signature_path = dir.join("tool.tar.gz.sig");
+        std::fs::write(&file_path, b"tool contents").unwrap();
+        let signature: Signature = signing_key.sign(b"tool contents");
+        std::fs::write(&signature_path, STANDARD.encode(signature.to_der())).unwrap();
+
+        let verification = Verification {
+            cosign: Some(CosignVerification {
+                key: Some(
+                    signing_key
+                        .verifying_key()
+                        .to_public_key_pem(LineEnding::LF)
+                        .unwrap(),
+                ),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let valid = verification.verify(SignatureKind::Cosign, &file_path, &signature_path, None);
+        std::fs::write(&file_path, b"tampered contents").unwrap();
+        let tampered =
+            verification.verify(SignatureKind::Cosign, &file_path, &signature_path, None);
+
+        assert!(valid.is_ok());
+        assert!(matches!(
+            tampered,
+            Err(SignatureError::VerificationFailed { .. })
+        ));
+    }
+}
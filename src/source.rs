@@ -0,0 +1,303 @@
+use crate::{
+    download::{self, DownloadAuth},
+    gitea::{GiteaError, GiteaSource},
+    github::{GitHubError, GitHubSource},
+    gitlab::{GitLabError, GitLabSource},
+    selection::{self, AssetScore},
+    system::System,
+    template::{TemplateError, TemplateSource, URL_PREFIX},
+    tool::Tool,
+    toolchain::{
+        CargoSource, GoSource, NpmSource, PipxSource, Toolchain, ToolchainError, CARGO_PREFIX,
+        GO_PREFIX, NPM_PREFIX, PIPX_PREFIX,
+    },
+    version::Version,
+};
+use async_trait::async_trait;
+use thiserror::Error;
+
+/// Prefix of tool names hosted on GitLab, `gitlab:group/subgroup/project`.
+pub const GITLAB_PREFIX: &str = "gitlab:";
+/// Prefix of tool names hosted on Gitea or Forgejo, `gitea:owner/repo`.
+pub const GITEA_PREFIX: &str = "gitea:";
+
+#[derive(Debug, Error)]
+pub enum SourceError {
+    #[error("'{0}' is not a valid tool name, expected `owner/repo`, `gitlab:group/project`, `gitea:owner/repo`, `url:name`, `cargo:crate`, `go:package`, `npm:package` or `pipx:package`")]
+    InvalidName(String),
+    #[error("Unable to find release {version} for {name}")]
+    VersionNotFound { name: String, version: Version },
+    #[error(transparent)]
+    GitHub(#[from] GitHubError),
+    #[error(transparent)]
+    GitLab(#[from] GitLabError),
+    #[error(transparent)]
+    Gitea(#[from] GiteaError),
+    #[error(transparent)]
+    Template(#[from] TemplateError),
+    #[error(transparent)]
+    Toolchain(#[from] ToolchainError),
+}
+
+pub type Result<T, E = SourceError> = std::result::Result<T, E>;
+
+/// A downloadable file published for a version of a tool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    pub name: String,
+    pub url: reqwest::Url,
+}
+
+/// Somewhere versions of a tool are published, every backend a tool can be installed from
+/// implements this.
+#[async_trait]
+pub trait ToolSource: std::fmt::Debug + Send + Sync {
+    /// The name of the project without its owner, used as the default alias.
+    fn default_alias(&self) -> &str;
+
+    /// Versions available to install, newest first.
+    async fn list_versions(&self, pre_release: bool) -> Result<Vec<String>>;
+
+    /// A page of the versions available to install, newest first, starting with page 1. Pages
+    /// after the last one are empty, sources listing every version at once only have one page.
+    async fn list_versions_page(&self, pre_release: bool, page: u32) -> Result<Vec<String>> {
+        match page {
+            1 => self.list_versions(pre_release).await,
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Resolve a requested version to the exact tag it is published under.
+    async fn resolve_version(&self, version: &'_ Version) -> Result<String>;
+
+    /// Every artifact published for a resolved tag.
+    async fn list_artifacts(&self, tag: &'_ str) -> Result<Vec<Artifact>>;
+
+    /// Score the artifacts of a release for a platform, best first.
+    fn score_artifacts(
+        &self,
+        artifacts: &'_ [Artifact],
+        system: &'_ System,
+        user_pattern: &'_ str,
+    ) -> Vec<AssetScore> {
+        selection::score_assets(artifacts, system, user_pattern)
+    }
+
+    /// Choose the artifact to install on a platform.
+    fn select_artifact(
+        &self,
+        artifacts: &'_ [Artifact],
+        system: &'_ System,
+        user_pattern: &'_ str,
+    ) -> Option<Artifact> {
+        selection::pick_asset(&self.score_artifacts(artifacts, system, user_pattern))
+    }
+
+    /// The toolchain installing the tool, for sources that publish packages instead of artifacts.
+    fn toolchain(&self) -> Option<&dyn Toolchain> {
+        None
+    }
+
+    /// The header authenticating downloads of the artifacts, for forges with a configured token.
+    fn download_auth(&self) -> Option<DownloadAuth> {
+        None
+    }
+}
+
+/// The source a tool from the environment is installed from, determined by its name. The tool's
+/// `base_url` points to a self-hosted instance of the forge instead of its public instance.
+pub fn for_tool(tool: &'_ Tool, system: &'_ System) -> Result<Box<dyn ToolSource>> {
+    let name = tool.name.as_str();
+    let invalid = || SourceError::InvalidName(name.to_string());
+    let base_url = tool.base_url.clone();
+    let owner_repo = |name: &str| match name.split_once('/') {
+        Some((owner, repo)) if !owner.is_empty() && !repo.is_empty() && !repo.contains('/') => {
+            Ok((owner.to_string(), repo.to_string()))
+        }
+        _ => Err(invalid()),
+    };
+    if let Some(name) = name.strip_prefix(URL_PREFIX) {
+        let template = tool
+            .template
+            .clone()
+            .ok_or_else(|| TemplateError::MissingTemplate(tool.name.to_string()))?;
+        if name.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Box::new(TemplateSource {
+            name: name.to_string(),
+            template,
+            system: system.clone(),
+        }))
+    } else if let Some(krate) = name.strip_prefix(CARGO_PREFIX) {
+        if krate.is_empty() || krate.contains('/') {
+            return Err(invalid());
+        }
+        Ok(Box::new(CargoSource {
+            krate: krate.to_string(),
+        }))
+    } else if let Some(package) = name.strip_prefix(GO_PREFIX) {
+        if package.is_empty() || package.split('/').any(str::is_empty) {
+            return Err(invalid());
+        }
+        Ok(Box::new(GoSource::new(package)))
+    } else if let Some(package) = name.strip_prefix(NPM_PREFIX) {
+        // only scoped packages, `@scope/name`, contain a slash
+        let valid = match package.strip_prefix('@') {
+            Some(scoped) => owner_repo(scoped).is_ok(),
+            None => !package.is_empty() && !package.contains('/'),
+        };
+        if !valid {
+            return Err(invalid());
+        }
+        Ok(Box::new(NpmSource {
+            package: package.to_string(),
+        }))
+    } else if let Some(package) = name.strip_prefix(PIPX_PREFIX) {
+        if package.is_empty() || package.contains('/') {
+            return Err(invalid());
+        }
+        Ok(Box::new(PipxSource {
+            package: package.to_string(),
+        }))
+    } else if let Some(project) = name.strip_prefix(GITLAB_PREFIX) {
+        // GitLab projects always live in a group or user namespace, subgroups can be nested
+        if project.split('/').count() < 2 || project.split('/').any(str::is_empty) {
+            return Err(invalid());
+        }
+        Ok(Box::new(GitLabSource {
+            project: project.to_string(),
+            base_url,
+        }))
+    } else if let Some(name) = name.strip_prefix(GITEA_PREFIX) {
+        let (owner, repo) = owner_repo(name)?;
+        Ok(Box::new(GiteaSource {
+            owner,
+            repo,
+            base_url,
+        }))
+    } else {
+        let (owner, repo) = owner_repo(name)?;
+        Ok(Box::new(GitHubSource {
+            owner,
+            repo,
+            base_url,
+        }))
+    }
+}
+
+/// Environment variable holding the token for a forge, `VERS_TOKEN_GIT_EXAMPLE_COM` for
+/// `https://git.example.com`.
+fn host_token_variable(host: &'_ str) -> String {
+    let host: String = host
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect();
+    format!("VERS_TOKEN_{host}")
+}
+
+/// The token sent to the forge a tool is hosted on. Environment files can be committed to any
+/// repository, so the configured token only goes to the configured instance, other hosts only
+/// get the token configured for them with `VERS_TOKEN_<HOST>`.
+pub fn token_for(
+    base_url: &'_ str,
+    configured_url: &'_ str,
+    configured_token: Option<&'_ str>,
+) -> Option<String> {
+    let host_of = |url: &str| {
+        reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| download::host_of(&url))
+    };
+    let host = host_of(base_url)?;
+    if Some(&host) == host_of(configured_url).as_ref() {
+        return configured_token.map(String::from);
+    }
+    std::env::var(host_token_variable(&host.0)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    #[test_case("https://gitlab.example.com", "https://gitlab.example.com/", Some("secret") ; "configured instance")]
+    #[test_case("https://GitLab.example.com:443/sub", "https://gitlab.example.com", Some("secret") ; "same host")]
+    #[test_case("https://attacker.example", "https://gitlab.example.com", None ; "foreign host")]
+    #[test_case("http://gitlab.example.com:8080", "https://gitlab.example.com", None ; "other port")]
+    fn token_for_tests(base_url: &'_ str, configured_url: &'_ str, expected: Option<&'_ str>) {
+        assert_eq!(
+            token_for(base_url, configured_url, Some("secret")),
+            expected.map(String::from)
+        )
+    }
+
+    #[test]
+    fn foreign_host_token() {
+        std::env::set_var("VERS_TOKEN_FORGE_VERS_TEST", "forge-token");
+        assert_eq!(
+            token_for(
+                "https://forge.vers.test",
+                "https://gitlab.com",
+                Some("secret")
+            ),
+            Some("forge-token".to_string())
+        );
+    }
+
+    fn tool(name: &'_ str, base_url: Option<&'_ str>) -> Tool {
+        Tool {
+            name: name.to_string(),
+            base_url: base_url.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test_case("cli/cli", None, Some(r#"GitHubSource { owner: "cli", repo: "cli", base_url: None }"#) ; "github")]
+    #[test_case("platform/deployer", Some("https://github.example.com"), Some(r#"GitHubSource { owner: "platform", repo: "deployer", base_url: Some("https://github.example.com") }"#) ; "github enterprise")]
+    #[test_case("gitlab:gitlab-org/cli", None, Some(r#"GitLabSource { project: "gitlab-org/cli", base_url: None }"#) ; "gitlab")]
+    #[test_case("gitlab:group/subgroup/project", None, Some(r#"GitLabSource { project: "group/subgroup/project", base_url: None }"#) ; "gitlab subgroup")]
+    #[test_case("gitlab:project", None, None ; "gitlab without namespace")]
+    #[test_case("gitlab:group//project", None, None ; "gitlab empty subgroup")]
+    #[test_case("gitea:gitea/tea", None, Some(r#"GiteaSource { owner: "gitea", repo: "tea", base_url: None }"#) ; "gitea")]
+    #[test_case("gitea:group/subgroup/project", None, None ; "gitea subgroup")]
+    #[test_case("cargo:ripgrep", None, Some(r#"CargoSource { krate: "ripgrep" }"#) ; "cargo")]
+    #[test_case("cargo:BurntSushi/ripgrep", None, None ; "cargo with owner")]
+    #[test_case("go:golang.org//gopls", None, None ; "go empty path segment")]
+    #[test_case("npm:@biomejs/biome", None, Some(r#"NpmSource { package: "@biomejs/biome" }"#) ; "npm scoped")]
+    #[test_case("npm:biomejs/biome", None, None ; "npm without scope")]
+    #[test_case("pipx:pre-commit", None, Some(r#"PipxSource { package: "pre-commit" }"#) ; "pipx")]
+    #[test_case("ripgrep", None, None ; "missing owner")]
+    #[test_case("url:kubectl", None, None ; "url without template")]
+    fn for_tool_tests(name: &'_ str, base_url: Option<&'_ str>, expected: Option<&'_ str>) {
+        assert_eq!(
+            for_tool(
+                &tool(name, base_url),
+                &"linux/x86_64".parse::<System>().unwrap()
+            )
+            .ok()
+            .map(|source| format!("{source:?}")),
+            expected.map(String::from)
+        )
+    }
+
+    #[test_case("cli/cli", "cli" ; "github")]
+    #[test_case("gitlab:group/subgroup/project", "project" ; "gitlab subgroup")]
+    #[test_case("gitea:gitea/tea", "tea" ; "gitea")]
+    #[test_case("cargo:cargo-nextest", "cargo-nextest" ; "cargo")]
+    #[test_case("go:golang.org/x/tools/cmd/stringer", "stringer" ; "go")]
+    #[test_case("npm:@biomejs/biome", "biome" ; "npm scoped")]
+    fn default_alias_tests(name: &'_ str, expected: &'_ str) {
+        assert_eq!(
+            for_tool(
+                &tool(name, None),
+                &"linux/x86_64".parse::<System>().unwrap()
+            )
+            .unwrap()
+            .default_alias(),
+            expected
+        )
+    }
+}
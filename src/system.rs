@@ -1,48 +1,223 @@
+use crate::archiver::ArchiveFormat;
 use regex::Regex;
 use std::{
     env::consts::{ARCH, OS},
     fmt::Display,
+    path::Path,
+    process::{Command, Stdio},
+    str::FromStr,
 };
+use thiserror::Error;
 use tracing::debug;
 
+#[derive(Debug, Error)]
+pub enum SystemError {
+    #[error("Unable to detect the platform of this {os}/{arch} machine, set one with --platform")]
+    UnsupportedHost { os: String, arch: String },
+    #[error("Invalid platform '{platform}', {message}")]
+    InvalidPlatform { platform: String, message: String },
+    #[error("Invalid libc of the {origin}, {message}")]
+    InvalidLibc { origin: String, message: String },
+}
+
+type Result<T, E = SystemError> = std::result::Result<T, E>;
+
 #[derive(Debug, Clone)]
 pub struct System {
     pub architecture: PlatformArchitecture,
     pub os: OperatingSystem,
+    /// C library of the system, only known on Linux when it could be detected.
+    pub libc: Option<Libc>,
 }
 
-impl Default for System {
-    fn default() -> Self {
-        Self {
-            architecture: match ARCH {
-                "x86" => PlatformArchitecture::I686,
-                "x86_64" => PlatformArchitecture::Amd64,
-                "arm" => PlatformArchitecture::Arm32,
-                "aarch64" => PlatformArchitecture::Arm64,
-                _ => panic!("Running on a unknown system architecture"),
-            },
-            os: match OS {
-                "linux" => OperatingSystem::Linux,
-                "macos" => OperatingSystem::Mac,
-                "windows" => OperatingSystem::Windows,
-                _ => panic!("Running on a unknown operating system"),
-            },
-        }
+/// Parses a platform in the form `os/arch`, like `linux/arm64` or `macos/x86_64`. The C library
+/// of Linux platforms is assumed to be glibc.
+impl FromStr for System {
+    type Err = SystemError;
+
+    fn from_str(s: &'_ str) -> Result<Self> {
+        let invalid = |message: String| SystemError::InvalidPlatform {
+            platform: s.to_string(),
+            message,
+        };
+        let (os, arch) = s
+            .split_once('/')
+            .ok_or_else(|| invalid("expected 'os/arch', for example 'linux/arm64'".to_string()))?;
+        let os = OperatingSystem::from_name(os)
+            .ok_or_else(|| invalid(format!("unknown operating system '{os}'")))?;
+        let architecture = PlatformArchitecture::from_name(arch)
+            .ok_or_else(|| invalid(format!("unknown architecture '{arch}'")))?;
+        Ok(Self {
+            libc: (os == OperatingSystem::Linux).then_some(Libc::Gnu),
+            architecture,
+            os,
+        })
     }
 }
 
 impl System {
+    /// The platform vers is running on.
+    pub fn host() -> Result<Self> {
+        let os = OperatingSystem::from_name(OS).ok_or_else(|| SystemError::UnsupportedHost {
+            os: OS.to_string(),
+            arch: ARCH.to_string(),
+        })?;
+        let architecture = PlatformArchitecture::host();
+        Ok(Self {
+            libc: (os == OperatingSystem::Linux).then(Libc::detect).flatten(),
+            architecture,
+            os,
+        })
+    }
+
+    /// Whether executables of this platform run on the machine vers is running on.
+    pub fn is_host(&self) -> bool {
+        OperatingSystem::from_name(OS).as_ref() == Some(&self.os)
+            && PlatformArchitecture::host() == self.architecture
+    }
+
+    /// Identifier of the platform in the form `os/arch`, for example `linux/x86_64`.
+    pub fn platform(&self) -> String {
+        format!("{}/{}", self.os.platform_name(), self.architecture)
+    }
+
     pub fn is_match(&self, s: &'_ str) -> bool {
+        self.matches_os(s) && self.matches_architecture(s)
+    }
+
+    /// Whether a file name names the operating system.
+    pub fn matches_os(&self, s: &'_ str) -> bool {
         let os_regex = self.os.get_match_regex();
-        let arch_regex = self.architecture.get_match_regex();
+        debug!("System OS Regex[{}], matching {}", os_regex.to_string(), s);
+        os_regex.is_match(s)
+    }
+
+    /// Whether a file name names the architecture, universal macOS binaries run on every Mac.
+    pub fn matches_architecture(&self, s: &'_ str) -> bool {
+        self.architecture.is_match(s) || self.matches_universal(s)
+    }
+
+    /// Whether a file name names a universal macOS binary that runs on this architecture.
+    pub fn matches_universal(&self, s: &'_ str) -> bool {
+        self.os == OperatingSystem::Mac
+            && matches!(
+                self.architecture,
+                PlatformArchitecture::Amd64 | PlatformArchitecture::Arm64
+            )
+            && token_regex("universal2?").is_match(s)
+    }
 
-        debug!(
-            "System OS Regex[{}], Arch Regex[{}], matching {}",
-            os_regex.to_string(),
-            arch_regex.to_string(),
-            s
-        );
-        os_regex.is_match(s) && arch_regex.is_match(s)
+    /// AppImages only run on Linux and often don't name an operating system, the ones that don't
+    /// name an architecture either are built for x86_64.
+    pub fn is_app_image_match(&self, s: &'_ str) -> bool {
+        if self.os != OperatingSystem::Linux
+            || ArchiveFormat::from_extension(Path::new(s)) != Some(ArchiveFormat::AppImage)
+        {
+            return false;
+        }
+        self.architecture.is_match(s)
+            || (self.architecture == PlatformArchitecture::Amd64
+                && !PlatformArchitecture::ALL
+                    .iter()
+                    .any(|architecture| architecture.is_match(s)))
+    }
+}
+
+/// C library executables are dynamically linked against on Linux.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Libc {
+    Gnu,
+    Musl,
+}
+
+impl Display for Libc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Libc::Gnu => write!(f, "glibc"),
+            Libc::Musl => write!(f, "musl"),
+        }
+    }
+}
+
+impl FromStr for Libc {
+    type Err = String;
+
+    fn from_str(s: &'_ str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gnu" | "glibc" => Ok(Self::Gnu),
+            "musl" => Ok(Self::Musl),
+            _ => Err(format!("unknown libc '{s}', expected 'gnu' or 'musl'")),
+        }
+    }
+}
+
+impl Libc {
+    /// Directories the dynamic loaders of glibc and musl are installed in.
+    const LOADER_DIRS: [&'static str; 4] = ["/lib", "/lib64", "/usr/lib", "/usr/lib64"];
+
+    /// Detect the C library from the dynamic loaders installed, `ldd` is asked when there are
+    /// none. Systems without glibc only run musl and static executables.
+    pub fn detect() -> Option<Self> {
+        let loaders: Vec<String> = Self::LOADER_DIRS
+            .iter()
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("ld-"))
+            .collect();
+        debug!("Dynamic loaders found: {loaders:?}");
+        Self::detect_from(&loaders, || {
+            Command::new("ldd")
+                .arg("--version")
+                .stdin(Stdio::null())
+                .output()
+                .ok()
+                .map(|output| {
+                    // musl prints its version to stderr
+                    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+                    text.push_str(&String::from_utf8_lossy(&output.stderr));
+                    text
+                })
+        })
+    }
+
+    /// The C library of the loaders found or else of the output of `ldd --version`, none when
+    /// neither names one so assets are selected as they would be for glibc.
+    fn detect_from<S: AsRef<str>>(
+        loaders: &'_ [S],
+        ldd_version: impl FnOnce() -> Option<String>,
+    ) -> Option<Self> {
+        let libc = Self::from_loaders(loaders)
+            .or_else(|| ldd_version().and_then(|output| Self::from_ldd_version(&output)));
+        match &libc {
+            Some(libc) => debug!("Detected libc {libc}"),
+            None => debug!("The libc could not be detected, neither a loader nor ldd names one"),
+        }
+        libc
+    }
+
+    /// glibc wins when both loaders are installed, it is the one the system is built with.
+    fn from_loaders<S: AsRef<str>>(loaders: &'_ [S]) -> Option<Self> {
+        let has_loader =
+            |prefix: &str| loaders.iter().any(|name| name.as_ref().starts_with(prefix));
+        if has_loader("ld-linux") {
+            Some(Self::Gnu)
+        } else if has_loader("ld-musl") {
+            Some(Self::Musl)
+        } else {
+            None
+        }
+    }
+
+    fn from_ldd_version(output: &'_ str) -> Option<Self> {
+        let output = output.to_lowercase();
+        if output.contains("musl") {
+            Some(Self::Musl)
+        } else if output.contains("glibc") || output.contains("gnu libc") {
+            Some(Self::Gnu)
+        } else {
+            None
+        }
     }
 }
 
@@ -64,6 +239,24 @@ impl Display for OperatingSystem {
 }
 
 impl OperatingSystem {
+    /// Operating system by its platform name or a common spelling of it.
+    pub fn from_name(name: &'_ str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "linux" => Some(Self::Linux),
+            "macos" | "darwin" | "mac" | "osx" => Some(Self::Mac),
+            "windows" | "win" => Some(Self::Windows),
+            _ => None,
+        }
+    }
+
+    pub fn platform_name(&self) -> &'static str {
+        match self {
+            Self::Linux => "linux",
+            Self::Mac => "macos",
+            Self::Windows => "windows",
+        }
+    }
+
     fn get_match_regex(&self) -> Regex {
         match self {
             Self::Linux => {
@@ -79,40 +272,220 @@ impl OperatingSystem {
     }
 }
 
+/// Regex matching one of the alternatives as a whole word of a file name, so `arm` doesn't match
+/// `arm64`. Everything but letters and digits separates words.
+fn token_regex(alternatives: &'_ str) -> Regex {
+    Regex::new(&format!(
+        r#"(?i)(?:^|[^a-z0-9])(?:{alternatives})(?:[^a-z0-9]|$)"#
+    ))
+    .expect("Unable to create regex for an architecture")
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum PlatformArchitecture {
     I686,
     Amd64,
     Arm32,
     Arm64,
+    Riscv64,
+    S390x,
+    Ppc64le,
+    /// An architecture vers has no table for, named the way Rust names it.
+    Other(String),
 }
 
 impl Display for PlatformArchitecture {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match *self {
+        match self {
             PlatformArchitecture::I686 => write!(f, "i686"),
             PlatformArchitecture::Amd64 => write!(f, "x86_64"),
             PlatformArchitecture::Arm32 => write!(f, "armv7"),
             PlatformArchitecture::Arm64 => write!(f, "arm64"),
+            PlatformArchitecture::Riscv64 => write!(f, "riscv64"),
+            PlatformArchitecture::S390x => write!(f, "s390x"),
+            PlatformArchitecture::Ppc64le => write!(f, "ppc64le"),
+            PlatformArchitecture::Other(name) => write!(f, "{name}"),
         }
     }
 }
 
 impl PlatformArchitecture {
+    const ALL: [PlatformArchitecture; 7] = [
+        Self::I686,
+        Self::Amd64,
+        Self::Arm32,
+        Self::Arm64,
+        Self::Riscv64,
+        Self::S390x,
+        Self::Ppc64le,
+    ];
+
+    /// Architecture by the name Rust or a release uses for it.
+    pub fn from_name(name: &'_ str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "x86" | "i386" | "i486" | "i586" | "i686" | "386" | "ia32" => Some(Self::I686),
+            "x86_64" | "x86-64" | "amd64" | "x64" => Some(Self::Amd64),
+            "arm" | "arm32" | "armv6" | "armv7" | "armv7l" | "armhf" | "armel" => Some(Self::Arm32),
+            "aarch64" | "arm64" | "armv8" => Some(Self::Arm64),
+            "riscv64" | "riscv64gc" => Some(Self::Riscv64),
+            "s390x" => Some(Self::S390x),
+            "ppc64le" | "ppc64el" | "powerpc64le" => Some(Self::Ppc64le),
+            _ => None,
+        }
+    }
+
+    /// The architecture vers is running on, Rust doesn't name the byte order of PowerPC.
+    fn host() -> Self {
+        match ARCH {
+            "powerpc64" if cfg!(target_endian = "little") => Self::Ppc64le,
+            arch => Self::from_name(arch).unwrap_or_else(|| {
+                debug!("No table for the architecture {arch}, matching assets by its name");
+                Self::Other(arch.to_string())
+            }),
+        }
+    }
+
+    /// Whether a file name names the architecture, in any of the spellings releases use. Ambiguous
+    /// names, like `64bit`, only count when no other architecture is named.
+    pub fn is_match(&self, s: &'_ str) -> bool {
+        let arch_regex = self.get_match_regex();
+        debug!("System Arch Regex[{}], matching {}", arch_regex, s);
+        if arch_regex.is_match(s) {
+            return true;
+        }
+        self.get_word_size_regex()
+            .map_or(false, |word_size_regex| word_size_regex.is_match(s))
+            && !Self::ALL
+                .iter()
+                .any(|other| other != self && other.get_match_regex().is_match(s))
+    }
+
     fn get_match_regex(&self) -> Regex {
         match self {
-            Self::I686 => {
-                Regex::new(r#"(?i).*i386|i686.*"#).expect("Unable to create regex for i686")
-            }
-            Self::Amd64 => {
-                Regex::new(r#"(?i).*amd64|x86_64.*"#).expect("Unable to create regex for amd64")
-            }
+            Self::I686 => token_regex(r#"i[3-6]86|386|ia32|win32"#),
+            Self::Amd64 => token_regex(r#"amd64|x86[_-]64|x64|win64"#),
             Self::Arm32 => {
-                Regex::new(r#"(?i).*arm32|armv6.*"#).expect("Unable to create regex for arm32")
-            }
-            Self::Arm64 => {
-                Regex::new(r#"(?i).*arm64|aarch64.*"#).expect("Unable to create regex for arm64")
+                token_regex(r#"arm32|armv[5-7](?:l|hf|hl)?|armhf|armel|gnueabihf|arm[_-]?32-?bit"#)
             }
+            Self::Arm64 => token_regex(r#"arm64e?|aarch64|armv8|aarch_64|arm[_-]?64-?bit"#),
+            Self::Riscv64 => token_regex(r#"riscv64(?:gc)?"#),
+            Self::S390x => token_regex(r#"s390x"#),
+            Self::Ppc64le => token_regex(r#"ppc64le|ppc64el|powerpc64le"#),
+            Self::Other(name) => token_regex(&regex::escape(name)),
+        }
+    }
+
+    /// Names that are also part of the names of other architectures, like the word size alone or
+    /// the `x86` of `x86_64`.
+    fn get_word_size_regex(&self) -> Option<Regex> {
+        match self {
+            Self::I686 => Some(token_regex(r#"x86|linux32|32-?bit"#)),
+            Self::Amd64 => Some(token_regex(r#"linux64|64-?bit"#)),
+            Self::Arm32 => Some(token_regex(r#"arm"#)),
+            _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    #[test_case(&["ld-linux-x86-64.so.2"], Some(Libc::Gnu) ; "glibc")]
+    #[test_case(&["ld-musl-x86_64.so.1"], Some(Libc::Musl) ; "musl")]
+    #[test_case(&["ld-musl-aarch64.so.1", "ld-linux-aarch64.so.1"], Some(Libc::Gnu) ; "both")]
+    #[test_case(&[], None ; "none")]
+    fn from_loaders_tests(loaders: &'_ [&'_ str], expected: Option<Libc>) {
+        assert_eq!(Libc::from_loaders(loaders), expected)
+    }
+
+    #[test_case(&[], Some("musl libc (x86_64)"), Some(Libc::Musl) ; "ldd")]
+    #[test_case(&["ld-linux-x86-64.so.2"], Some("musl libc (x86_64)"), Some(Libc::Gnu) ; "loader first")]
+    #[test_case(&[], Some("ldd: unknown option"), None ; "unknown ldd")]
+    #[test_case(&[], None, None ; "undetected")]
+    fn detect_from_tests(
+        loaders: &'_ [&'_ str],
+        ldd_version: Option<&'_ str>,
+        expected: Option<Libc>,
+    ) {
+        assert_eq!(
+            Libc::detect_from(loaders, || ldd_version.map(String::from)),
+            expected
+        )
+    }
+
+    #[test_case("ldd (Ubuntu GLIBC 2.35-0ubuntu3.1) 2.35", Some(Libc::Gnu) ; "glibc")]
+    #[test_case("musl libc (x86_64)\nVersion 1.2.4", Some(Libc::Musl) ; "musl")]
+    #[test_case("", None ; "unknown")]
+    fn from_ldd_version_tests(output: &'_ str, expected: Option<Libc>) {
+        assert_eq!(Libc::from_ldd_version(output), expected)
+    }
+
+    #[test_case("linux/arm64", Some("linux/arm64") ; "linux")]
+    #[test_case("darwin/amd64", Some("macos/x86_64") ; "alternative spellings")]
+    #[test_case("windows/i686", Some("windows/i686") ; "windows")]
+    #[test_case("linux", None ; "missing architecture")]
+    #[test_case("linux/sparc", None ; "unknown architecture")]
+    #[test_case("plan9/x86_64", None ; "unknown operating system")]
+    fn platform_from_str_tests(platform: &'_ str, expected: Option<&'_ str>) {
+        assert_eq!(
+            platform
+                .parse::<System>()
+                .ok()
+                .map(|system| system.platform()),
+            expected.map(String::from)
+        )
+    }
+
+    #[test_case(PlatformArchitecture::Amd64, "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz", true ; "x86_64 target triple")]
+    #[test_case(PlatformArchitecture::Amd64, "nvim-linux64.tar.gz", true ; "linux64")]
+    #[test_case(PlatformArchitecture::Amd64, "tool-win-x64.zip", true ; "x64")]
+    #[test_case(PlatformArchitecture::I686, "tool_linux_386.tar.gz", true ; "386")]
+    #[test_case(PlatformArchitecture::I686, "tool-x86_64-linux.tar.gz", false ; "x86 prefix of x86_64")]
+    #[test_case(PlatformArchitecture::I686, "tool-linux-x86.tar.gz", true ; "x86")]
+    #[test_case(PlatformArchitecture::I686, "tool-arm-32bit.tar.gz", false ; "32bit of arm")]
+    #[test_case(PlatformArchitecture::Amd64, "tool-linux-64bit.tar.gz", true ; "64bit")]
+    #[test_case(PlatformArchitecture::Amd64, "tool-linux-arm-64bit.tar.gz", false ; "64bit of arm")]
+    #[test_case(PlatformArchitecture::Amd64, "tool-linux64-arm64.tar.gz", false ; "linux64 of arm64")]
+    #[test_case(PlatformArchitecture::Arm32, "tool-arm-32bit.tar.gz", true ; "arm 32bit")]
+    #[test_case(PlatformArchitecture::Arm32, "tool-linux-arm-64bit.tar.gz", false ; "arm of arm 64bit")]
+    #[test_case(PlatformArchitecture::Arm64, "tool-linux-arm-64bit.tar.gz", true ; "arm 64bit")]
+    #[test_case(PlatformArchitecture::Arm32, "tool-arm-unknown-linux-gnueabihf.tar.gz", true ; "arm target triple")]
+    #[test_case(PlatformArchitecture::Arm32, "tool_linux_armv7l.tar.gz", true ; "armv7l")]
+    #[test_case(PlatformArchitecture::Arm32, "tool_linux_armhf.deb", true ; "armhf")]
+    #[test_case(PlatformArchitecture::Arm32, "tool_linux_arm64.tar.gz", false ; "arm prefix of arm64")]
+    #[test_case(PlatformArchitecture::Arm64, "tool-aarch64-apple-darwin.tar.gz", true ; "aarch64")]
+    #[test_case(PlatformArchitecture::Riscv64, "tool-riscv64gc-unknown-linux-gnu.tar.gz", true ; "riscv64gc")]
+    #[test_case(PlatformArchitecture::S390x, "tool_linux_s390x.tar.gz", true ; "s390x")]
+    #[test_case(PlatformArchitecture::Ppc64le, "tool-powerpc64le-unknown-linux-gnu.tar.gz", true ; "powerpc64le")]
+    #[test_case(PlatformArchitecture::Other("loongarch64".to_string()), "tool-loongarch64-linux.tar.gz", true ; "other")]
+    fn architecture_is_match_tests(
+        architecture: PlatformArchitecture,
+        name: &'_ str,
+        expected: bool,
+    ) {
+        assert_eq!(architecture.is_match(name), expected)
+    }
+
+    #[test_case(OperatingSystem::Mac, PlatformArchitecture::Arm64, true ; "apple silicon")]
+    #[test_case(OperatingSystem::Mac, PlatformArchitecture::Amd64, true ; "intel mac")]
+    #[test_case(OperatingSystem::Linux, PlatformArchitecture::Amd64, false ; "linux")]
+    fn universal_tests(os: OperatingSystem, architecture: PlatformArchitecture, expected: bool) {
+        let system = System {
+            architecture,
+            os,
+            libc: None,
+        };
+        assert_eq!(
+            system.matches_architecture("tool-darwin-universal.tar.gz"),
+            expected
+        )
+    }
+
+    #[test_case("gnu", Ok(Libc::Gnu) ; "gnu")]
+    #[test_case("MUSL", Ok(Libc::Musl) ; "musl")]
+    #[test_case("uclibc", Err(()) ; "unknown")]
+    fn libc_from_str_tests(s: &'_ str, expected: Result<Libc, ()>) {
+        assert_eq!(s.parse::<Libc>().map_err(|_| ()), expected)
+    }
+}
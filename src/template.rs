@@ -0,0 +1,370 @@
+use crate::{
+    github,
+    selection::{AssetScore, Reason},
+    source::{self, Artifact, SourceError, ToolSource},
+    system::System,
+    version::{parse_version, Version},
+};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Prefix of tool names downloaded from a URL template, `url:kubectl`.
+pub const URL_PREFIX: &str = "url:";
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("No URL template is configured for {0}")]
+    MissingTemplate(String),
+    #[error("Unknown placeholder '{{{placeholder}}}' in URL template '{template}'")]
+    UnknownPlaceholder {
+        template: String,
+        placeholder: String,
+    },
+    #[error("Invalid URL '{url}'. {message}")]
+    InvalidUrl { url: String, message: String },
+    #[error("Invalid version pattern '{pattern}'. {message}")]
+    InvalidPattern { pattern: String, message: String },
+    #[error("Failed to get '{url}': {source}")]
+    Request { url: String, source: reqwest::Error },
+    #[error("Failed to get '{url}': server responded with {status}")]
+    HttpStatus {
+        url: String,
+        status: reqwest::StatusCode,
+    },
+}
+
+type Result<T, E = TemplateError> = std::result::Result<T, E>;
+
+/// Download location of a tool that isn't published as release assets, for example
+/// `https://dl.k8s.io/release/v{version}/bin/{os}/{arch}/kubectl`.
+///
+/// `{version}` is replaced with the version without a leading `v`, `{os}` and `{arch}` with the
+/// spelling of the current platform. Go style spellings (`linux`, `darwin`, `windows`, `amd64`,
+/// `arm64`, `386`, `arm`) are used unless the tool maps them to its own.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub struct UrlTemplate {
+    pub url: String,
+    /// Spelling of the operating system by platform name (`linux`, `macos`, `windows`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub os: BTreeMap<String, String>,
+    /// Spelling of the architecture by platform name (`x86_64`, `arm64`, `i686`, `armv7`,
+    /// `riscv64`, `s390x`, `ppc64le`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub arch: BTreeMap<String, String>,
+    /// Where the available versions are listed
+    #[serde(default)]
+    pub versions: VersionList,
+}
+
+/// How the versions of a templated tool are discovered.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionList {
+    /// Only explicitly requested versions can be installed
+    #[default]
+    None,
+    /// Release tags of a GitHub repository, `owner/repo`
+    #[serde(rename = "github")]
+    GitHub(String),
+    /// Versions listed in a document, every line of it or every match of the first capture group
+    /// of `pattern`
+    Url {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pattern: Option<String>,
+    },
+}
+
+impl UrlTemplate {
+    /// Expand the template for a version on a platform.
+    pub fn expand(&self, version: &'_ str, system: &'_ System) -> Result<String> {
+        let os_name = system.os.platform_name();
+        let arch_name = system.architecture.to_string();
+        let os = self
+            .os
+            .get(os_name)
+            .map(String::as_str)
+            .unwrap_or(match os_name {
+                "macos" => "darwin",
+                other => other,
+            });
+        let arch =
+            self.arch
+                .get(&arch_name)
+                .map(String::as_str)
+                .unwrap_or(match arch_name.as_str() {
+                    "x86_64" => "amd64",
+                    "i686" => "386",
+                    "armv7" => "arm",
+                    other => other,
+                });
+
+        let placeholder_regex = Regex::new(r#"\{([^{}]*)\}"#).expect("valid placeholder regex");
+        let mut unknown = None;
+        let url =
+            placeholder_regex.replace_all(&self.url, |caps: &regex::Captures| match &caps[1] {
+                "version" => version.trim_start_matches('v').to_string(),
+                "os" => os.to_string(),
+                "arch" => arch.to_string(),
+                other => {
+                    unknown.get_or_insert_with(|| other.to_string());
+                    String::new()
+                }
+            });
+        match unknown {
+            Some(placeholder) => Err(TemplateError::UnknownPlaceholder {
+                template: self.url.to_string(),
+                placeholder,
+            }),
+            None => Ok(url.to_string()),
+        }
+    }
+}
+
+impl VersionList {
+    /// Available versions, newest first when they can be parsed as SemVer.
+    async fn list(&self, pre_release: bool) -> source::Result<Vec<String>> {
+        let mut versions = match self {
+            VersionList::None => Vec::new(),
+            VersionList::GitHub(repo) => {
+                let (owner, repo) = repo
+                    .split_once('/')
+                    .ok_or_else(|| SourceError::InvalidName(repo.to_string()))?;
+                let mut versions = Vec::new();
+                for page in 1.. {
+                    let page_versions =
+                        github::get_repo_releases(None, owner, repo, pre_release, page).await?;
+                    if page_versions.is_empty() {
+                        break;
+                    }
+                    versions.extend(page_versions);
+                }
+                versions
+            }
+            VersionList::Url { url, pattern } => {
+                parse_versions(&fetch_text(url).await?, pattern.as_deref())?
+            }
+        };
+        if !pre_release {
+            versions
+                .retain(|v| !matches!(parse_version(v), Version::SemVer(s) if !s.pre.is_empty()));
+        }
+        // documents are often sorted oldest first, or not at all
+        let (mut semver, others): (Vec<_>, Vec<_>) = versions
+            .into_iter()
+            .map(|v| match parse_version(&v) {
+                Version::SemVer(parsed) => (Some(parsed), v),
+                _ => (None, v),
+            })
+            .partition(|(parsed, _)| parsed.is_some());
+        semver.sort_by(|(a, _), (b, _)| b.cmp(a));
+        let mut versions: Vec<String> = semver.into_iter().chain(others).map(|(_, v)| v).collect();
+        versions.dedup();
+        Ok(versions)
+    }
+}
+
+async fn fetch_text(url: &'_ str) -> Result<String> {
+    let to_request_err = |source| TemplateError::Request {
+        url: url.to_string(),
+        source,
+    };
+    let response = reqwest::get(url).await.map_err(to_request_err)?;
+    if !response.status().is_success() {
+        return Err(TemplateError::HttpStatus {
+            url: url.to_string(),
+            status: response.status(),
+        });
+    }
+    response.text().await.map_err(to_request_err)
+}
+
+/// Find the versions in a document, without a pattern every non-empty line is a version.
+fn parse_versions(contents: &'_ str, pattern: Option<&'_ str>) -> Result<Vec<String>> {
+    match pattern {
+        Some(pattern) => {
+            let regex = Regex::new(pattern).map_err(|regex_err| TemplateError::InvalidPattern {
+                pattern: pattern.to_string(),
+                message: regex_err.to_string(),
+            })?;
+            Ok(regex
+                .captures_iter(contents)
+                .filter_map(|caps| caps.get(1).or_else(|| caps.get(0)))
+                .map(|m| m.as_str().to_string())
+                .collect())
+        }
+        None => Ok(contents
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(String::from)
+            .collect()),
+    }
+}
+
+/// A tool downloaded from a URL built from a template.
+#[derive(Debug)]
+pub struct TemplateSource {
+    pub name: String,
+    pub template: UrlTemplate,
+    pub system: System,
+}
+
+#[async_trait]
+impl ToolSource for TemplateSource {
+    fn default_alias(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or(&self.name)
+    }
+
+    async fn list_versions(&self, pre_release: bool) -> source::Result<Vec<String>> {
+        self.template.versions.list(pre_release).await
+    }
+
+    async fn resolve_version(&self, version: &'_ Version) -> source::Result<String> {
+        let versions = self.template.versions.list(false).await?;
+        if version == &Version::Latest {
+            return versions
+                .into_iter()
+                .next()
+                .ok_or_else(|| SourceError::VersionNotFound {
+                    name: self.name.to_string(),
+                    version: version.clone(),
+                });
+        }
+        match versions.into_iter().find(|v| &parse_version(v) == version) {
+            Some(listed) => Ok(listed),
+            None => {
+                warn!(
+                    "{} is not listed for {}, trying to download it anyway",
+                    version, &self.name
+                );
+                Ok(version.as_tag())
+            }
+        }
+    }
+
+    async fn list_artifacts(&self, tag: &'_ str) -> source::Result<Vec<Artifact>> {
+        let url = self.template.expand(tag, &self.system)?;
+        info!(
+            "Expanded URL template for {}@{} to {}",
+            &self.name, tag, url
+        );
+        let url = reqwest::Url::parse(&url).map_err(|parse_err| TemplateError::InvalidUrl {
+            url: url.to_string(),
+            message: parse_err.to_string(),
+        })?;
+        let name = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&self.name)
+            .to_string();
+        Ok(vec![Artifact { name, url }])
+    }
+
+    /// The URL is already specific to the platform, there is nothing to choose from.
+    fn score_artifacts(
+        &self,
+        artifacts: &'_ [Artifact],
+        _system: &'_ System,
+        _user_pattern: &'_ str,
+    ) -> Vec<AssetScore> {
+        artifacts
+            .iter()
+            .map(|artifact| AssetScore {
+                artifact: artifact.clone(),
+                points: 0,
+                eligible: true,
+                reasons: vec![Reason {
+                    points: 0,
+                    description: "is expanded from the URL template for the platform".to_string(),
+                }],
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::system::{OperatingSystem, PlatformArchitecture},
+        test_case::test_case,
+    };
+
+    fn system(os: OperatingSystem, architecture: PlatformArchitecture) -> System {
+        System {
+            architecture,
+            os,
+            libc: None,
+        }
+    }
+
+    #[test_case("https://dl.k8s.io/release/v{version}/bin/{os}/{arch}/kubectl", "v1.28.2", system(OperatingSystem::Linux, PlatformArchitecture::Amd64), "https://dl.k8s.io/release/v1.28.2/bin/linux/amd64/kubectl" ; "kubectl")]
+    #[test_case("https://releases.hashicorp.com/terraform/{version}/terraform_{version}_{os}_{arch}.zip", "1.5.7", system(OperatingSystem::Mac, PlatformArchitecture::Arm64), "https://releases.hashicorp.com/terraform/1.5.7/terraform_1.5.7_darwin_arm64.zip" ; "terraform")]
+    #[test_case("https://go.dev/dl/go{version}.{os}-{arch}.tar.gz", "1.21.1", system(OperatingSystem::Linux, PlatformArchitecture::I686), "https://go.dev/dl/go1.21.1.linux-386.tar.gz" ; "go")]
+    fn expand_tests(url: &'_ str, version: &'_ str, system: System, expected: &'_ str) {
+        let template = UrlTemplate {
+            url: url.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(template.expand(version, &system).unwrap(), expected)
+    }
+
+    #[test]
+    fn expand_with_spelling_maps() {
+        let template = UrlTemplate {
+            url: "https://example.com/{version}/tool-{os}-{arch}".to_string(),
+            os: BTreeMap::from([("macos".to_string(), "Darwin".to_string())]),
+            arch: BTreeMap::from([("arm64".to_string(), "aarch64".to_string())]),
+            ..Default::default()
+        };
+        assert_eq!(
+            template
+                .expand(
+                    "v2.0.0",
+                    &system(OperatingSystem::Mac, PlatformArchitecture::Arm64)
+                )
+                .unwrap(),
+            "https://example.com/2.0.0/tool-Darwin-aarch64"
+        )
+    }
+
+    #[test]
+    fn expand_unknown_placeholder() {
+        let template = UrlTemplate {
+            url: "https://example.com/{version}/{platform}".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            template.expand(
+                "1.0.0",
+                &system(OperatingSystem::Linux, PlatformArchitecture::Amd64)
+            ),
+            Err(TemplateError::UnknownPlaceholder { placeholder, .. }) if placeholder == "platform"
+        ))
+    }
+
+    #[test_case("v1.28.2\n", None, vec!["v1.28.2"] ; "single line")]
+    #[test_case(r#"<a href="/terraform/1.5.7/">terraform_1.5.7</a><a href="/terraform/1.6.0-beta1/">terraform_1.6.0-beta1</a>"#, Some(r#"terraform_(\d+\.\d+\.\d+[^<]*)"#), vec!["1.5.7", "1.6.0-beta1"] ; "pattern")]
+    fn parse_versions_tests(contents: &'_ str, pattern: Option<&'_ str>, expected: Vec<&'_ str>) {
+        assert_eq!(parse_versions(contents, pattern).unwrap(), expected)
+    }
+
+    #[test]
+    fn deserialize_template() {
+        let template: UrlTemplate = serde_json::from_str(
+            r#"{
+                "url": "https://get.helm.sh/helm-v{version}-{os}-{arch}.tar.gz",
+                "versions": { "github": "helm/helm" }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(template.versions, VersionList::GitHub("helm/helm".into()));
+    }
+}
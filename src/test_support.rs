@@ -0,0 +1,35 @@
+//! Helpers shared by the tests of several modules.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static NEXT_TEMP_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// A directory created under the temporary directory of the system, removed with everything in it
+/// when dropped, so it is cleaned up when a test fails too.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create a directory named after the test using it, unique across the tests of every run.
+    pub fn new(name: &'_ str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "vers-{name}-{}-{}",
+            std::process::id(),
+            NEXT_TEMP_DIR.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).expect("Unable to create a temporary directory");
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
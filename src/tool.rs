@@ -1,5 +1,34 @@
-use crate::version::Version;
+use crate::{signature::Verification, template::UrlTemplate, version::Version};
 use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, str::FromStr};
+
+/// An executable shipped next to the main one of a tool, `go` ships `gofmt` for example.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Binary {
+    /// File name of the executable inside the tool's directory
+    pub file_pattern: String,
+    /// Name the executable is linked as in the environment
+    pub link: String,
+}
+
+impl FromStr for Binary {
+    type Err = String;
+
+    /// Parse `PATTERN` or `PATTERN=LINK`, the executable is linked under its own name unless a
+    /// link name is provided.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (file_pattern, link) = s.split_once('=').unwrap_or((s, s));
+        if file_pattern.is_empty() || link.is_empty() || link.contains('/') {
+            return Err(format!(
+                "'{s}' is not a valid binary, expected `PATTERN` or `PATTERN=LINK`"
+            ));
+        }
+        Ok(Self {
+            file_pattern: file_pattern.to_string(),
+            link: link.to_string(),
+        })
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 #[serde(rename_all = "snake_case", default)]
@@ -10,6 +39,31 @@ pub struct Tool {
     pub file_pattern: String,
     pub current_version: String,
     pub installed_versions: Vec<String>,
+    /// Requirement the version has to satisfy, like `^1.4`, updates stay within it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_req: Option<String>,
+    /// URL of the self-hosted forge (GitHub Enterprise Server, GitLab, Gitea or Forgejo) the tool
+    /// is released on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// Download location of tools that are not published as release assets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<UrlTemplate>,
+    /// Archive or directory the tool was installed from instead of being downloaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_file: Option<PathBuf>,
+    /// Source building the tool when a release has no asset for the platform, `cargo:ripgrep`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
+    /// Executables linked into the environment next to the main one
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub binaries: Vec<Binary>,
+    /// Executables declared by a package installed with a toolchain, like the `bin` of an npm
+    /// package or the console scripts of a Python project
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub entry_points: Vec<String>,
+    #[serde(skip_serializing_if = "Verification::is_empty")]
+    pub verify: Verification,
 }
 
 impl Tool {
@@ -28,9 +82,47 @@ impl Tool {
             file_pattern: file_pattern.to_string(),
             current_version: version.clone(),
             installed_versions: vec![version],
+            version_req: None,
+            base_url: None,
+            template: None,
+            from_file: None,
+            fallback: None,
+            binaries: Vec::new(),
+            entry_points: Vec::new(),
+            verify: Verification::default(),
         }
     }
 
+    /// Every executable linked into the environment, starting with the main one. Declared entry
+    /// points are all linked under their own names, the one matching the file pattern is linked
+    /// as the alias.
+    pub fn links(&self) -> Vec<Binary> {
+        let main = Binary {
+            file_pattern: if self.file_pattern.is_empty() {
+                self.alias.to_string()
+            } else {
+                self.file_pattern.to_string()
+            },
+            link: self.alias.to_string(),
+        };
+        let main_file = main.file_pattern.to_string();
+        // packages like `typescript` don't have an entry point named after them
+        let main = (self.entry_points.is_empty() || self.entry_points.contains(&main_file))
+            .then_some(main);
+        let entry_points = self
+            .entry_points
+            .iter()
+            .filter(|entry_point| **entry_point != main_file)
+            .map(|entry_point| Binary {
+                file_pattern: entry_point.to_string(),
+                link: entry_point.to_string(),
+            });
+        main.into_iter()
+            .chain(entry_points)
+            .chain(self.binaries.iter().cloned())
+            .collect()
+    }
+
     pub fn set_current_version(&mut self, version: &'_ Version) {
         self.current_version = version.to_string()
     }
@@ -40,3 +132,50 @@ impl Tool {
         self.installed_versions.push(version.as_tag())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    #[test_case("gofmt", Some(("gofmt", "gofmt")) ; "file name")]
+    #[test_case("kubectl-convert=kconvert", Some(("kubectl-convert", "kconvert")) ; "link name")]
+    #[test_case("=gofmt", None ; "empty pattern")]
+    #[test_case("gofmt=", None ; "empty link")]
+    #[test_case("gofmt=bin/gofmt", None ; "link in directory")]
+    fn binary_from_str_tests(s: &'_ str, expected: Option<(&'_ str, &'_ str)>) {
+        assert_eq!(
+            s.parse::<Binary>().ok(),
+            expected.map(|(file_pattern, link)| Binary {
+                file_pattern: file_pattern.to_string(),
+                link: link.to_string(),
+            })
+        )
+    }
+
+    #[test]
+    fn links_start_with_main_binary() {
+        let mut tool = Tool::new("golang/go", "go", &Version::Latest, "", "");
+        tool.binaries = vec!["gofmt".parse().unwrap()];
+        assert_eq!(
+            tool.links()
+                .into_iter()
+                .map(|binary| binary.link)
+                .collect::<Vec<_>>(),
+            vec!["go", "gofmt"]
+        )
+    }
+
+    #[test_case("typescript", &["tsc", "tsserver"], &["tsc", "tsserver"] ; "no entry point named like the package")]
+    #[test_case("httpie", &["http", "httpie", "https"], &["httpie", "http", "https"] ; "entry point named like the package")]
+    fn links_of_entry_points(alias: &'_ str, entry_points: &[&str], expected: &[&str]) {
+        let mut tool = Tool::new(alias, alias, &Version::Latest, "", alias);
+        tool.entry_points = entry_points.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            tool.links()
+                .into_iter()
+                .map(|binary| binary.link)
+                .collect::<Vec<_>>(),
+            expected
+        )
+    }
+}
@@ -0,0 +1,158 @@
+use super::Toolchain;
+use crate::{
+    source::{self, Artifact, SourceError, ToolSource},
+    version::Version,
+};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tracing::info;
+
+/// Prefix of tools built from a crate published to crates.io, `cargo:ripgrep`.
+pub const CARGO_PREFIX: &str = "cargo:";
+
+const CRATES_IO_API_URL: &str = "https://crates.io/api/v1/crates";
+
+#[derive(Debug, Deserialize)]
+struct CrateResponse {
+    #[serde(rename = "crate")]
+    krate: CrateInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateInfo {
+    max_version: String,
+    max_stable_version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateVersions {
+    versions: Vec<CrateVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateVersion {
+    num: String,
+    #[serde(default)]
+    yanked: bool,
+}
+
+impl CrateVersions {
+    /// Versions that can still be installed, crates.io lists them newest first.
+    fn installable(self, pre_release: bool) -> Vec<String> {
+        self.versions
+            .into_iter()
+            .filter(|version| !version.yanked)
+            .filter(|version| {
+                pre_release
+                    || semver::Version::parse(&version.num).map_or(true, |v| v.pre.is_empty())
+            })
+            .map(|version| version.num)
+            .collect()
+    }
+}
+
+/// A crate built and installed with `cargo install`.
+#[derive(Debug)]
+pub struct CargoSource {
+    pub krate: String,
+}
+
+impl CargoSource {
+    async fn versions(&self, pre_release: bool) -> source::Result<Vec<String>> {
+        let url = format!("{CRATES_IO_API_URL}/{}/versions", self.krate);
+        Ok(super::get_json::<CrateVersions>(&url)
+            .await?
+            .installable(pre_release))
+    }
+}
+
+#[async_trait]
+impl ToolSource for CargoSource {
+    fn default_alias(&self) -> &str {
+        &self.krate
+    }
+
+    async fn list_versions(&self, pre_release: bool) -> source::Result<Vec<String>> {
+        self.versions(pre_release).await
+    }
+
+    async fn resolve_version(&self, version: &'_ Version) -> source::Result<String> {
+        info!("Getting version({}) of crate {}", version, &self.krate);
+        if version == &Version::Latest {
+            let url = format!("{CRATES_IO_API_URL}/{}", self.krate);
+            let info = super::get_json::<CrateResponse>(&url).await?.krate;
+            return Ok(info.max_stable_version.unwrap_or(info.max_version));
+        }
+        let tag = version.as_tag();
+        self.versions(true)
+            .await?
+            .into_iter()
+            .find(|num| num == &tag)
+            .ok_or_else(|| SourceError::VersionNotFound {
+                name: self.krate.to_string(),
+                version: version.clone(),
+            })
+    }
+
+    async fn list_artifacts(&self, _tag: &'_ str) -> source::Result<Vec<Artifact>> {
+        // crates are built from source, there is nothing to download
+        Ok(Vec::new())
+    }
+
+    fn toolchain(&self) -> Option<&dyn Toolchain> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl Toolchain for CargoSource {
+    async fn install(&self, version: &'_ str, out_dir: &'_ Path) -> super::Result<PathBuf> {
+        // respect the cargo of a toolchain selected by rustup or the calling cargo
+        let mut command = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+        command
+            .arg("install")
+            .arg(&self.krate)
+            .arg("--version")
+            .arg(format!("={}", version.trim_start_matches('v')))
+            .arg("--root")
+            .arg(out_dir)
+            .args(["--locked", "--force"]);
+        super::run(command).await?;
+        Ok(out_dir.join("bin"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_from_api_response() {
+        let versions = serde_json::from_str::<CrateVersions>(
+            r#"{
+                "versions": [
+                    { "id": 4, "crate": "ripgrep", "num": "14.0.0-pre.1", "yanked": false },
+                    { "id": 3, "crate": "ripgrep", "num": "13.0.1", "yanked": true },
+                    { "id": 2, "crate": "ripgrep", "num": "13.0.0", "yanked": false },
+                    { "id": 1, "crate": "ripgrep", "num": "12.1.1", "yanked": false }
+                ],
+                "meta": { "total": 4 }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(versions.installable(false), vec!["13.0.0", "12.1.1"]);
+    }
+
+    #[test]
+    fn latest_from_api_response() {
+        let response = serde_json::from_str::<CrateResponse>(
+            r#"{ "crate": { "id": "ripgrep", "max_version": "14.0.0-pre.1", "max_stable_version": "13.0.0" } }"#,
+        )
+        .unwrap();
+        assert_eq!(response.krate.max_stable_version.as_deref(), Some("13.0.0"));
+    }
+}
@@ -0,0 +1,214 @@
+use super::{Toolchain, ToolchainError};
+use crate::{
+    source::{self, Artifact, SourceError, ToolSource},
+    version::Version,
+};
+use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tracing::{debug, info};
+
+/// Prefix of tools installed with `go install`, `go:golang.org/x/tools/gopls`.
+pub const GO_PREFIX: &str = "go:";
+
+const DEFAULT_GOPROXY: &str = "https://proxy.golang.org";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ModuleInfo {
+    version: String,
+}
+
+/// A Go package with a main function, built and installed with `go install`.
+#[derive(Debug)]
+pub struct GoSource {
+    pub package: String,
+    /// The module providing the package, found by asking the module proxy.
+    module: OnceCell<String>,
+}
+
+impl GoSource {
+    pub fn new(package: &'_ str) -> Self {
+        Self {
+            package: package.to_string(),
+            module: OnceCell::new(),
+        }
+    }
+
+    /// The module containing the package, the longest prefix of the package path that the module
+    /// proxy knows about.
+    async fn module(&self) -> source::Result<&str> {
+        if let Some(module) = self.module.get() {
+            return Ok(module);
+        }
+        let proxy = goproxy();
+        let mut candidate = self.package.as_str();
+        loop {
+            let url = format!("{proxy}/{}/@latest", escape(candidate));
+            match super::get_json::<ModuleInfo>(&url).await {
+                Ok(_) => break,
+                Err(ToolchainError::HttpStatus { status, .. })
+                    if status == reqwest::StatusCode::NOT_FOUND
+                        || status == reqwest::StatusCode::GONE =>
+                {
+                    debug!("{candidate} is not a module");
+                    match candidate.rsplit_once('/') {
+                        Some((parent, _)) => candidate = parent,
+                        None => {
+                            return Err(SourceError::VersionNotFound {
+                                name: self.package.to_string(),
+                                version: Version::Latest,
+                            })
+                        }
+                    }
+                }
+                Err(proxy_err) => return Err(proxy_err.into()),
+            }
+        }
+        Ok(self.module.get_or_init(|| candidate.to_string()))
+    }
+}
+
+#[async_trait]
+impl ToolSource for GoSource {
+    fn default_alias(&self) -> &str {
+        binary_name(&self.package)
+    }
+
+    async fn list_versions(&self, pre_release: bool) -> source::Result<Vec<String>> {
+        let url = format!("{}/{}/@v/list", goproxy(), escape(self.module().await?));
+        Ok(sorted_versions(&super::get_text(&url).await?, pre_release))
+    }
+
+    async fn resolve_version(&self, version: &'_ Version) -> source::Result<String> {
+        info!(
+            "Getting version({}) of Go package {}",
+            version, &self.package
+        );
+        let module = escape(self.module().await?);
+        let url = match version {
+            Version::Latest => format!("{}/{module}/@latest", goproxy()),
+            _ => {
+                let tag = version.as_tag();
+                // module versions always start with a v, branches and commits resolve as well
+                let query = if tag.starts_with(|c: char| c.is_ascii_digit()) {
+                    format!("v{tag}")
+                } else {
+                    tag
+                };
+                format!("{}/{module}/@v/{}.info", goproxy(), escape(&query))
+            }
+        };
+        Ok(super::get_json::<ModuleInfo>(&url).await?.version)
+    }
+
+    async fn list_artifacts(&self, _tag: &'_ str) -> source::Result<Vec<Artifact>> {
+        // packages are built from source, there is nothing to download
+        Ok(Vec::new())
+    }
+
+    fn toolchain(&self) -> Option<&dyn Toolchain> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl Toolchain for GoSource {
+    async fn install(&self, version: &'_ str, out_dir: &'_ Path) -> super::Result<PathBuf> {
+        let mut command = Command::new("go");
+        command
+            .arg("install")
+            .arg(format!("{}@{version}", self.package))
+            .env("GOBIN", out_dir);
+        super::run(command).await?;
+        Ok(out_dir.to_path_buf())
+    }
+}
+
+/// The first proxy configured in `GOPROXY` that can be queried over HTTP.
+fn goproxy() -> String {
+    std::env::var("GOPROXY")
+        .ok()
+        .and_then(|goproxy| {
+            goproxy
+                .split([',', '|'])
+                .find(|proxy| proxy.starts_with("https://") || proxy.starts_with("http://"))
+                .map(|proxy| proxy.trim_end_matches('/').to_string())
+        })
+        .unwrap_or_else(|| DEFAULT_GOPROXY.to_string())
+}
+
+/// Module paths and versions are case-encoded for the proxy, upper case letters are replaced by
+/// an exclamation mark followed by the lower case letter.
+fn escape(path: &'_ str) -> String {
+    path.chars()
+        .map(|c| {
+            if c.is_ascii_uppercase() {
+                format!("!{}", c.to_ascii_lowercase())
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+/// The name `go install` gives the binary of a package, major version suffixes are skipped.
+fn binary_name(package: &'_ str) -> &str {
+    let mut segments = package.rsplit('/');
+    let last = segments.next().unwrap_or(package);
+    let is_major_version =
+        last.len() > 1 && last.starts_with('v') && last[1..].chars().all(|c| c.is_ascii_digit());
+    match segments.next() {
+        Some(parent) if is_major_version => parent,
+        _ => last,
+    }
+}
+
+/// The proxy lists versions unordered, newest first like the other sources.
+fn sorted_versions(list: &'_ str, pre_release: bool) -> Vec<String> {
+    let mut versions: Vec<(semver::Version, &str)> = list
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| {
+            semver::Version::parse(line.trim_start_matches('v'))
+                .ok()
+                .map(|v| (v, line))
+        })
+        .filter(|(v, _)| pre_release || v.pre.is_empty())
+        .collect();
+    versions.sort_by(|a, b| b.0.cmp(&a.0));
+    versions.into_iter().map(|(_, line)| line.into()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    #[test_case("golang.org/x/tools/gopls", "gopls" ; "package")]
+    #[test_case("github.com/go-delve/delve/cmd/dlv", "dlv" ; "command in a module")]
+    #[test_case("github.com/golangci/golangci-lint/v2/cmd/golangci-lint", "golangci-lint" ; "major version module")]
+    #[test_case("github.com/google/go-jsonnet/v2", "go-jsonnet" ; "major version suffix")]
+    fn binary_name_tests(package: &'_ str, expected: &'_ str) {
+        assert_eq!(binary_name(package), expected)
+    }
+
+    #[test]
+    fn escape_upper_case() {
+        assert_eq!(
+            escape("github.com/BurntSushi/toml"),
+            "github.com/!burnt!sushi/toml"
+        )
+    }
+
+    #[test]
+    fn versions_newest_first() {
+        assert_eq!(
+            sorted_versions("v0.9.0\nv0.10.1\nv0.11.0-pre.1\nv0.10.0\n", false),
+            vec!["v0.10.1", "v0.10.0", "v0.9.0"]
+        )
+    }
+}
@@ -0,0 +1,119 @@
+pub use self::{
+    cargo::{CargoSource, CARGO_PREFIX},
+    go::{GoSource, GO_PREFIX},
+    npm::{NpmSource, NPM_PREFIX},
+    pipx::{PipxSource, PIPX_PREFIX},
+};
+use async_trait::async_trait;
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+};
+use thiserror::Error;
+use tracing::info;
+
+mod cargo;
+mod go;
+mod npm;
+mod pipx;
+
+#[derive(Debug, Error)]
+pub enum ToolchainError {
+    #[error("Failed to run '{program}', is it installed? {source}")]
+    Spawn {
+        program: String,
+        source: std::io::Error,
+    },
+    #[error("'{command}' failed with {status}. {stderr}")]
+    CommandFailed {
+        command: String,
+        status: ExitStatus,
+        stderr: String,
+    },
+    #[error("Failed to get '{url}': {source}")]
+    Request { url: String, source: reqwest::Error },
+    #[error("Failed to get '{url}': server responded with {status}")]
+    HttpStatus {
+        url: String,
+        status: reqwest::StatusCode,
+    },
+    #[error("Failed to deserialize the response of '{url}'. {msg}")]
+    Deserialization { url: String, msg: String },
+}
+
+type Result<T, E = ToolchainError> = std::result::Result<T, E>;
+
+/// Installs a version of a tool with a language toolchain, used by sources that publish
+/// packages instead of release artifacts.
+#[async_trait]
+pub trait Toolchain: std::fmt::Debug + Send + Sync {
+    /// Install a resolved version into `out_dir`, returns the directory the tool's entry points
+    /// were installed to.
+    async fn install(&self, version: &'_ str, out_dir: &'_ Path) -> Result<PathBuf>;
+
+    /// Names of the executables the installed version declares, they are all linked into the
+    /// environment. Tools of toolchains without declared entry points are linked by their alias.
+    async fn entry_points(&self, _version: &'_ str, _out_dir: &'_ Path) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Run a toolchain command to completion, the output is only shown when it fails.
+async fn run(mut command: Command) -> Result<()> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let command_line = std::iter::once(program.clone())
+        .chain(
+            command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().to_string()),
+        )
+        .collect::<Vec<_>>()
+        .join(" ");
+    info!("Running `{command_line}`");
+    let output = async_std::task::spawn_blocking(move || command.output())
+        .await
+        .map_err(|spawn_err| ToolchainError::Spawn {
+            program,
+            source: spawn_err,
+        })?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(ToolchainError::CommandFailed {
+            command: command_line,
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        })
+    }
+}
+
+/// Get a document from a package registry, registries like crates.io reject requests without a
+/// user agent.
+async fn get_text(url: &'_ str) -> Result<String> {
+    let to_request_err = |source| ToolchainError::Request {
+        url: url.to_string(),
+        source,
+    };
+    let response = reqwest::Client::builder()
+        .user_agent(concat!("vers/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(to_request_err)?
+        .get(url)
+        .send()
+        .await
+        .map_err(to_request_err)?;
+    if !response.status().is_success() {
+        return Err(ToolchainError::HttpStatus {
+            url: url.to_string(),
+            status: response.status(),
+        });
+    }
+    response.text().await.map_err(to_request_err)
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(url: &'_ str) -> Result<T> {
+    serde_json::from_str(&get_text(url).await?).map_err(|de_err| ToolchainError::Deserialization {
+        url: url.to_string(),
+        msg: de_err.to_string(),
+    })
+}
@@ -0,0 +1,206 @@
+use super::Toolchain;
+use crate::{
+    source::{self, Artifact, SourceError, ToolSource},
+    version::Version,
+};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tracing::info;
+
+/// Prefix of tools installed from the npm registry, `npm:prettier` or `npm:@biomejs/biome`.
+pub const NPM_PREFIX: &str = "npm:";
+
+const NPM_REGISTRY_URL: &str = "https://registry.npmjs.org";
+
+#[derive(Debug, Deserialize)]
+struct Packument {
+    #[serde(rename = "dist-tags", default)]
+    dist_tags: BTreeMap<String, String>,
+    #[serde(default)]
+    versions: BTreeMap<String, PackageVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackageVersion {
+    #[serde(default)]
+    bin: Option<PackageBin>,
+}
+
+/// The executables of a package, a single one named after the package or a map of names to
+/// scripts.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PackageBin {
+    Single(String),
+    Named(BTreeMap<String, String>),
+}
+
+impl Packument {
+    /// Published versions newest first, the registry keys them by version.
+    fn sorted_versions(&self, pre_release: bool) -> Vec<String> {
+        let mut versions: Vec<semver::Version> = self
+            .versions
+            .keys()
+            .filter_map(|version| semver::Version::parse(version).ok())
+            .filter(|version| pre_release || version.pre.is_empty())
+            .collect();
+        versions.sort_by(|a, b| b.cmp(a));
+        versions.iter().map(ToString::to_string).collect()
+    }
+
+    /// Names of the executables a version of the package declares in its `bin`.
+    fn entry_points(&self, package: &'_ str, version: &'_ str) -> Vec<String> {
+        match self.versions.get(version).and_then(|v| v.bin.as_ref()) {
+            // a single script is named after the package without its scope
+            Some(PackageBin::Single(_)) => {
+                vec![package.rsplit('/').next().unwrap_or(package).to_string()]
+            }
+            Some(PackageBin::Named(bin)) => bin.keys().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// A Node.js package installed into a private prefix with `npm install --global`.
+#[derive(Debug)]
+pub struct NpmSource {
+    pub package: String,
+}
+
+impl NpmSource {
+    async fn packument(&self) -> super::Result<Packument> {
+        // the slash of scoped packages is part of the name, not the path
+        let url = format!("{NPM_REGISTRY_URL}/{}", self.package.replace('/', "%2f"));
+        super::get_json(&url).await
+    }
+}
+
+#[async_trait]
+impl ToolSource for NpmSource {
+    fn default_alias(&self) -> &str {
+        self.package.rsplit('/').next().unwrap_or(&self.package)
+    }
+
+    async fn list_versions(&self, pre_release: bool) -> source::Result<Vec<String>> {
+        Ok(self.packument().await?.sorted_versions(pre_release))
+    }
+
+    async fn resolve_version(&self, version: &'_ Version) -> source::Result<String> {
+        info!(
+            "Getting version({}) of npm package {}",
+            version, &self.package
+        );
+        let packument = self.packument().await?;
+        let tag = version.as_tag();
+        // dist-tags like `latest` or `next` point to a version
+        let resolved = match packument.dist_tags.get(&tag) {
+            Some(tagged) => Some(tagged.to_string()),
+            None if packument.versions.contains_key(&tag) => Some(tag),
+            None => None,
+        };
+        resolved.ok_or_else(|| SourceError::VersionNotFound {
+            name: self.package.to_string(),
+            version: version.clone(),
+        })
+    }
+
+    async fn list_artifacts(&self, _tag: &'_ str) -> source::Result<Vec<Artifact>> {
+        // packages are installed by npm, there is nothing to download
+        Ok(Vec::new())
+    }
+
+    fn toolchain(&self) -> Option<&dyn Toolchain> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl Toolchain for NpmSource {
+    async fn install(&self, version: &'_ str, out_dir: &'_ Path) -> super::Result<PathBuf> {
+        let mut command = Command::new("npm");
+        command
+            .args(["install", "--global", "--no-fund", "--no-audit", "--prefix"])
+            .arg(out_dir)
+            .arg(format!("{}@{version}", self.package));
+        super::run(command).await?;
+        // the packages' `bin` entries are linked into the prefix
+        Ok(out_dir.join("bin"))
+    }
+
+    async fn entry_points(
+        &self,
+        version: &'_ str,
+        _out_dir: &'_ Path,
+    ) -> super::Result<Vec<String>> {
+        Ok(self.packument().await?.entry_points(&self.package, version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_from_packument() {
+        let packument = serde_json::from_str::<Packument>(
+            r#"{
+                "name": "prettier",
+                "dist-tags": { "latest": "3.0.3", "next": "3.1.0-beta.1" },
+                "versions": {
+                    "2.8.8": { "bin": { "prettier": "bin-prettier.js" } },
+                    "3.0.3": { "bin": { "prettier": "bin/prettier.cjs" } },
+                    "3.1.0-beta.1": { "bin": { "prettier": "bin/prettier.cjs" } },
+                    "3.0.10": { "bin": { "prettier": "bin/prettier.cjs" } }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(packument.dist_tags["latest"], "3.0.3");
+        assert_eq!(
+            packument.sorted_versions(false),
+            vec!["3.0.10", "3.0.3", "2.8.8"]
+        );
+    }
+
+    #[test]
+    fn entry_points_from_packument() {
+        let packument = serde_json::from_str::<Packument>(
+            r#"{
+                "name": "typescript",
+                "dist-tags": { "latest": "5.2.2" },
+                "versions": {
+                    "5.2.2": { "bin": { "tsc": "bin/tsc", "tsserver": "bin/tsserver" } },
+                    "5.3.0-beta": {}
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            packument.entry_points("typescript", "5.2.2"),
+            vec!["tsc", "tsserver"]
+        );
+        assert!(packument
+            .entry_points("typescript", "5.3.0-beta")
+            .is_empty());
+    }
+
+    #[test]
+    fn entry_point_of_single_bin() {
+        let packument = serde_json::from_str::<Packument>(
+            r#"{
+                "name": "@biomejs/biome",
+                "versions": { "1.4.1": { "bin": "bin/biome" } }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            packument.entry_points("@biomejs/biome", "1.4.1"),
+            vec!["biome"]
+        );
+    }
+}
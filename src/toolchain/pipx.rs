@@ -0,0 +1,288 @@
+use super::Toolchain;
+use crate::{
+    source::{self, Artifact, SourceError, ToolSource},
+    version::Version,
+};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tracing::{debug, info};
+use walkdir::WalkDir;
+
+/// Prefix of Python applications installed from PyPI into a virtual environment of their own,
+/// `pipx:ruff`.
+pub const PIPX_PREFIX: &str = "pipx:";
+
+const PYPI_URL: &str = "https://pypi.org/pypi";
+
+/// The PEP 440 segments that may follow the release numbers, `rc1`, `.post2`, `a1.dev3` and
+/// the like.
+static VERSION_SUFFIX_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)^(?P<pre>[._-]?(a|alpha|b|beta|c|rc|pre|preview)[._-]?\d*)?([._-]?(post|rev|r)[._-]?\d*|-\d+)?(?P<dev>[._-]?dev[._-]?\d*)?$"#,
+    )
+    .expect("unable to create regex for version suffixes")
+});
+
+#[derive(Debug, Deserialize)]
+struct PypiProject {
+    info: PypiInfo,
+    #[serde(default)]
+    releases: BTreeMap<String, Vec<PypiFile>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PypiInfo {
+    version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PypiFile {
+    #[serde(default)]
+    yanked: bool,
+}
+
+impl PypiProject {
+    /// Releases with files that weren't yanked, newest first.
+    fn sorted_versions(&self, pre_release: bool) -> Vec<String> {
+        let mut versions: Vec<(Vec<u64>, &String)> = self
+            .releases
+            .iter()
+            .filter(|(_, files)| files.iter().any(|file| !file.yanked))
+            .filter_map(|(version, _)| {
+                let (release, is_pre_release) = release_segments(version)?;
+                (pre_release || !is_pre_release).then_some((release, version))
+            })
+            .collect();
+        versions.sort_by(|a, b| b.0.cmp(&a.0));
+        versions
+            .into_iter()
+            .map(|(_, version)| version.to_string())
+            .collect()
+    }
+}
+
+/// The numeric release segments of a PEP 440 version and whether it is an alpha, beta, release
+/// candidate or development release. The local version label after a `+` is ignored.
+fn release_segments(version: &'_ str) -> Option<(Vec<u64>, bool)> {
+    let version = version
+        .split_once('+')
+        .map_or(version, |(public, _)| public);
+    let end = version
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(version.len());
+    let release: Vec<u64> = version[..end]
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.parse().ok())
+        .collect::<Option<_>>()?;
+    if release.is_empty() {
+        return None;
+    }
+    let suffix = VERSION_SUFFIX_REGEX.captures(&version[end..])?;
+    let is_pre_release = suffix.name("pre").is_some() || suffix.name("dev").is_some();
+    Some((release, is_pre_release))
+}
+
+/// Distribution names compared the way pip names `.dist-info` directories, case insensitive and
+/// with `-` and `.` replaced by `_`.
+fn normalize_name(name: &'_ str) -> String {
+    name.to_lowercase().replace(['-', '.'], "_")
+}
+
+/// Names of the `console_scripts` in the `entry_points.txt` of an installed distribution.
+fn console_scripts(entry_points: &'_ str) -> Vec<String> {
+    let mut in_console_scripts = false;
+    let mut scripts = Vec::new();
+    for line in entry_points.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_console_scripts = line == "[console_scripts]";
+        } else if in_console_scripts && !line.is_empty() && !line.starts_with('#') {
+            if let Some((name, _)) = line.split_once('=') {
+                scripts.push(name.trim().to_string());
+            }
+        }
+    }
+    scripts
+}
+
+/// A Python application installed into a private virtual environment, like pipx does.
+#[derive(Debug)]
+pub struct PipxSource {
+    pub package: String,
+}
+
+impl PipxSource {
+    async fn project(&self) -> source::Result<PypiProject> {
+        Ok(super::get_json(&format!("{PYPI_URL}/{}/json", self.package)).await?)
+    }
+}
+
+#[async_trait]
+impl ToolSource for PipxSource {
+    fn default_alias(&self) -> &str {
+        &self.package
+    }
+
+    async fn list_versions(&self, pre_release: bool) -> source::Result<Vec<String>> {
+        Ok(self.project().await?.sorted_versions(pre_release))
+    }
+
+    async fn resolve_version(&self, version: &'_ Version) -> source::Result<String> {
+        info!(
+            "Getting version({}) of PyPI project {}",
+            version, &self.package
+        );
+        let project = self.project().await?;
+        if version == &Version::Latest {
+            return Ok(project.info.version);
+        }
+        let tag = version.as_tag();
+        if project.releases.contains_key(&tag) {
+            Ok(tag)
+        } else {
+            Err(SourceError::VersionNotFound {
+                name: self.package.to_string(),
+                version: version.clone(),
+            })
+        }
+    }
+
+    async fn list_artifacts(&self, _tag: &'_ str) -> source::Result<Vec<Artifact>> {
+        // projects are installed by pip, there is nothing to download
+        Ok(Vec::new())
+    }
+
+    fn toolchain(&self) -> Option<&dyn Toolchain> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl Toolchain for PipxSource {
+    async fn install(&self, version: &'_ str, out_dir: &'_ Path) -> super::Result<PathBuf> {
+        let mut venv = Command::new("python3");
+        venv.args(["-m", "venv", "--clear"]).arg(out_dir);
+        super::run(venv).await?;
+
+        let bin_dir = out_dir.join("bin");
+        let mut pip = Command::new(bin_dir.join("python"));
+        pip.args([
+            "-m",
+            "pip",
+            "install",
+            "--disable-pip-version-check",
+            "--no-input",
+        ])
+        .arg(format!("{}=={version}", self.package));
+        super::run(pip).await?;
+        // the project's console scripts are installed next to the interpreter of the venv
+        Ok(bin_dir)
+    }
+
+    async fn entry_points(
+        &self,
+        _version: &'_ str,
+        out_dir: &'_ Path,
+    ) -> super::Result<Vec<String>> {
+        let package = normalize_name(&self.package);
+        // `lib/python3.x/site-packages/<name>-<version>.dist-info/entry_points.txt`
+        let dist_info = WalkDir::new(out_dir.join("lib"))
+            .max_depth(3)
+            .into_iter()
+            .filter_map(Result::ok)
+            .find(|entry| {
+                let file_name = entry.file_name().to_string_lossy();
+                entry.file_type().is_dir()
+                    && file_name.ends_with(".dist-info")
+                    && file_name
+                        .split_once('-')
+                        .map_or(false, |(name, _)| normalize_name(name) == package)
+            });
+        let entry_points = match dist_info {
+            Some(dist_info) => dist_info.path().join("entry_points.txt"),
+            None => {
+                debug!("No installed distribution of {} was found", &self.package);
+                return Ok(Vec::new());
+            }
+        };
+        match std::fs::read_to_string(&entry_points) {
+            Ok(contents) => Ok(console_scripts(&contents)),
+            Err(read_err) => {
+                debug!("Unable to read {}. {read_err}", entry_points.display());
+                Ok(Vec::new())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case};
+
+    #[test_case("1.2.3", Some((vec![1, 2, 3], false)) ; "release")]
+    #[test_case("2.0", Some((vec![2, 0], false)) ; "two segments")]
+    #[test_case("0.1.0rc1", Some((vec![0, 1, 0], true)) ; "release candidate")]
+    #[test_case("3.0.0.dev4", Some((vec![3, 0, 0], true)) ; "development release")]
+    #[test_case("1.0.post1", Some((vec![1, 0], false)) ; "post release")]
+    #[test_case("1.0b2", Some((vec![1, 0], true)) ; "beta")]
+    #[test_case("1.0a1.dev2", Some((vec![1, 0], true)) ; "development alpha")]
+    #[test_case("1.0.post1.dev2", Some((vec![1, 0], true)) ; "development post release")]
+    #[test_case("1.0+abc", Some((vec![1, 0], false)) ; "local version")]
+    #[test_case("1.0+cuda", Some((vec![1, 0], false)) ; "local version with pre release letters")]
+    #[test_case("2.1rc1+cpu", Some((vec![2, 1], true)) ; "local release candidate")]
+    #[test_case("1.0-1", Some((vec![1, 0], false)) ; "implicit post release")]
+    #[test_case("1.0banana", None ; "unknown suffix")]
+    #[test_case("latest", None ; "not a version")]
+    fn release_segments_tests(version: &'_ str, expected: Option<(Vec<u64>, bool)>) {
+        assert_eq!(release_segments(version), expected)
+    }
+
+    #[test]
+    fn console_scripts_from_entry_points() {
+        let entry_points = r#"
+            [console_scripts]
+            http = httpie.__main__:main
+            https = httpie.__main__:main
+            httpie = httpie.manager.__main__:main
+
+            [gui_scripts]
+            httpie-gui = httpie.gui:main
+        "#;
+        assert_eq!(
+            console_scripts(entry_points),
+            vec!["http", "https", "httpie"]
+        );
+    }
+
+    #[test_case("pre-commit", "pre_commit" ; "dash")]
+    #[test_case("Zope.Interface", "zope_interface" ; "dot and case")]
+    fn normalize_name_tests(name: &'_ str, expected: &'_ str) {
+        assert_eq!(normalize_name(name), expected)
+    }
+
+    #[test]
+    fn versions_from_api_response() {
+        let project = serde_json::from_str::<PypiProject>(
+            r#"{
+                "info": { "name": "ruff", "version": "0.1.5" },
+                "releases": {
+                    "0.0.292": [{ "filename": "ruff-0.0.292.tar.gz", "yanked": false }],
+                    "0.1.5": [{ "filename": "ruff-0.1.5.tar.gz", "yanked": false }],
+                    "0.1.10": [],
+                    "0.1.4": [{ "filename": "ruff-0.1.4.tar.gz", "yanked": true }],
+                    "0.2.0a1": [{ "filename": "ruff-0.2.0a1.tar.gz", "yanked": false }]
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(project.info.version, "0.1.5");
+        assert_eq!(project.sorted_versions(false), vec!["0.1.5", "0.0.292"]);
+    }
+}
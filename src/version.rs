@@ -1,7 +1,7 @@
 use std::fmt::Display;
 use tracing::info;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Version {
     /// The latest version of a tool as determined by the tool managers
     Latest,
@@ -15,6 +15,9 @@ pub enum Version {
     PreRelease,
     ///
     Simple(String),
+    /// A range of versions like `^1.4`, `~2.10` or `>=0.9,<1.0`, installs the highest release that
+    /// satisfies it.
+    Requirement(semver::VersionReq),
 }
 
 impl Default for Version {
@@ -28,6 +31,7 @@ impl Version {
         match self {
             Version::SemVer(v) => v.to_string(),
             Version::Simple(s) => s.to_string(),
+            Version::Requirement(r) => r.to_string(),
             Version::Latest => "latest".to_string(),
             Version::Lts => "lts".to_string(),
             Version::Stable => "stable".to_string(),
@@ -53,6 +57,8 @@ pub fn parse_version(provided_version: &'_ str) -> Version {
             info!("Parsing version: {}", provided_version);
             if let Ok(parsed_semver) = semver::Version::parse(provided_version) {
                 Version::SemVer(parsed_semver)
+            } else if let Some(requirement) = parse_requirement(provided_version) {
+                Version::Requirement(requirement)
             } else {
                 Version::Simple(provided_version.to_string())
             }
@@ -60,6 +66,32 @@ pub fn parse_version(provided_version: &'_ str) -> Version {
     }
 }
 
+/// Only versions starting with an operator or combining comparators are requirements, `1.4` on
+/// its own stays the name of a tag.
+fn parse_requirement(provided_version: &'_ str) -> Option<semver::VersionReq> {
+    if !provided_version.starts_with(['^', '~', '<', '>', '=']) && !provided_version.contains(',') {
+        return None;
+    }
+    semver::VersionReq::parse(provided_version).ok()
+}
+
+/// The highest of the tags that satisfies a requirement, tags that aren't semantic versions are
+/// skipped.
+pub fn highest_matching<'a>(
+    requirement: &'_ semver::VersionReq,
+    tags: &'a [String],
+) -> Option<&'a str> {
+    tags.iter()
+        .filter_map(|tag| {
+            semver::Version::parse(tag.trim_start_matches('v'))
+                .ok()
+                .filter(|version| requirement.matches(version))
+                .map(|version| (version, tag.as_str()))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, tag)| tag)
+}
+
 #[cfg(test)]
 mod tests {
     use {super::*, test_case::test_case};
@@ -91,10 +123,35 @@ mod tests {
     #[test_case("3.2.1+fd3f4b7a5a331a2384ed13fb3ead44e975438c3b",
                 Version::SemVer(semver::Version{ major: 3, minor: 2, patch: 1, pre: semver::Prerelease::EMPTY, build: semver::BuildMetadata::new("fd3f4b7a5a331a2384ed13fb3ead44e975438c3b").unwrap() });
                 "SemVer: with metadata")]
+    #[test_case("^1.4", Version::Requirement(semver::VersionReq::parse("^1.4").unwrap()) ; "Requirement: caret")]
+    #[test_case("~2.10", Version::Requirement(semver::VersionReq::parse("~2.10").unwrap()) ; "Requirement: tilde")]
+    #[test_case(">=0.9,<1.0", Version::Requirement(semver::VersionReq::parse(">=0.9, <1.0").unwrap()) ; "Requirement: range")]
+    #[test_case("1.4", Version::Simple("1.4".to_string()) ; "Simple: partial version")]
     fn parse_version_testing(input: &'_ str, expected: Version) {
         assert_eq!(parse_version(input), expected)
     }
 
+    #[test_case("^1.4", Some("v1.6.2") ; "caret")]
+    #[test_case("~1.4", Some("v1.4.10") ; "tilde")]
+    #[test_case(">=0.9,<1.0", Some("0.9.1") ; "range")]
+    #[test_case("^3", None ; "no match")]
+    fn highest_matching_tests(requirement: &'_ str, expected: Option<&'_ str>) {
+        let tags = [
+            "v1.6.2",
+            "v2.0.0",
+            "v1.4.10",
+            "v1.4.9",
+            "0.9.1",
+            "nightly",
+            "v1.7.0-rc.1",
+        ]
+        .map(String::from);
+        assert_eq!(
+            highest_matching(&semver::VersionReq::parse(requirement).unwrap(), &tags),
+            expected
+        )
+    }
+
     #[test_case(Version::Latest, "latest" ; "Latest tag")]
     #[test_case(Version::Stable, "stable" ; "Stable tag")]
     #[test_case(Version::Lts, "lts" ; "LTS tag")]